@@ -0,0 +1,377 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+use super::err::Source;
+use super::instruction::{Instruction, Operand, OperandKind};
+use super::program::Program;
+use super::vobj::{IntRange, Vobj, MAX_LIST_DEPTH};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AsmError {
+    pub line: u32,
+    pub message: String,
+    pub src: Source,
+}
+
+impl AsmError {
+    fn new(src: Source, message: String) -> Self {
+        Self {
+            line: src.line_number(),
+            message,
+            src,
+        }
+    }
+}
+
+impl fmt::Display for AsmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for AsmError {}
+
+/// Assembles `.zt` source text into instructions.
+///
+/// Each line holds at most one instruction, optionally preceded by a
+/// `label:` and followed by a `;` comment. Branch operands are either an
+/// instruction index or a label name.
+pub fn assemble(text: &str, source_file: &str) -> Result<Vec<Instruction>, AsmError> {
+    assemble_from(text, source_file, 1)
+}
+
+/// Like [`assemble`], numbering source lines from `first_line`.
+pub fn assemble_from(
+    text: &str,
+    source_file: &str,
+    first_line: u32,
+) -> Result<Vec<Instruction>, AsmError> {
+    assemble_with_labels(text, source_file, first_line).map(|(code, _)| code)
+}
+
+/// Like [`assemble`], keeping label names as debug information.
+pub fn assemble_program(text: &str, source_file: &str) -> Result<Program, AsmError> {
+    let (code, labels) = assemble_with_labels(text, source_file, 1)?;
+    let mut program = Program::new(code);
+    for (label, index) in labels {
+        program = program.with_label(index, &label);
+    }
+    Ok(program)
+}
+
+// An instruction whose operand can only be resolved once every label is known.
+struct Pending<'t> {
+    line_number: u32,
+    column: u32,
+    len: u32,
+    mnemonic: &'t str,
+    operand: &'t str,
+}
+
+fn assemble_with_labels(
+    text: &str,
+    source_file: &str,
+    first_line: u32,
+) -> Result<(Vec<Instruction>, HashMap<String, usize>), AsmError> {
+    let source_file: Arc<str> = Arc::from(source_file);
+    let mut labels: HashMap<String, usize> = HashMap::new();
+    let mut pending: Vec<Pending> = Vec::new();
+
+    for (index, raw_line) in text.lines().enumerate() {
+        let line_number = first_line + index as u32;
+        let mut line = strip_comment(raw_line).trim();
+        while let Some((label, rest)) = split_label(line) {
+            if labels.insert(label.to_string(), pending.len()).is_some() {
+                let offset = label.as_ptr() as usize - raw_line.as_ptr() as usize;
+                let src = Source::with_span(
+                    Arc::clone(&source_file),
+                    line_number,
+                    raw_line[..offset].chars().count() as u32 + 1,
+                    label.chars().count() as u32,
+                );
+                return Err(AsmError::new(src, format!("duplicate label `{}`", label)));
+            }
+            line = rest;
+        }
+        if line.is_empty() {
+            continue;
+        }
+        let (mnemonic, operand) = match line.split_once(char::is_whitespace) {
+            Some((mnemonic, operand)) => (mnemonic, operand.trim()),
+            None => (line, ""),
+        };
+        // `line` is a slice of `raw_line`, so their distance is its byte offset
+        let offset = line.as_ptr() as usize - raw_line.as_ptr() as usize;
+        pending.push(Pending {
+            line_number,
+            column: raw_line[..offset].chars().count() as u32 + 1,
+            len: line.chars().count() as u32,
+            mnemonic,
+            operand,
+        });
+    }
+
+    let mut code = Vec::with_capacity(pending.len());
+    for pending in pending {
+        let src = Source::with_span(
+            Arc::clone(&source_file),
+            pending.line_number,
+            pending.column,
+            pending.len,
+        );
+        let kind = Instruction::operand_kind(pending.mnemonic).ok_or_else(|| {
+            AsmError::new(
+                src.clone(),
+                format!("unknown instruction `{}`", pending.mnemonic),
+            )
+        })?;
+        let operand = parse_operand(kind, pending.operand, &labels)
+            .map_err(|message| AsmError::new(src.clone(), message))?;
+        let instruction = Instruction::from_parts(pending.mnemonic, operand, src.clone())
+            .map_err(|message| AsmError::new(src, message))?;
+        code.push(instruction);
+    }
+    Ok((code, labels))
+}
+
+/// Renders a program as an indexed listing, one instruction per line.
+pub fn disassemble(code: &[Instruction]) -> String {
+    let mut listing = String::new();
+    for (index, instruction) in code.iter().enumerate() {
+        let text = format_instruction(instruction);
+        listing.push_str(&format!(
+            "{:>4}  {:<24} ; {}\n",
+            index,
+            text,
+            instruction.src()
+        ));
+    }
+    listing
+}
+
+/// Formats a single instruction in the syntax accepted by [`assemble`].
+pub fn format_instruction(instruction: &Instruction) -> String {
+    let name = instruction.name().to_ascii_lowercase();
+    match instruction.operand() {
+        Operand::None => name,
+        Operand::Value(value) => format!("{} {}", name, format_value(&value)),
+        Operand::Index(index) => format!("{} {}", name, index),
+        Operand::Name(operand) => format!("{} {}", name, operand),
+    }
+}
+
+pub fn format_value(value: &Vobj) -> String {
+    match value {
+        Vobj::Str(value) => {
+            let mut literal = String::from("\"");
+            for c in value.chars() {
+                match c {
+                    '"' => literal.push_str("\\\""),
+                    '\\' => literal.push_str("\\\\"),
+                    '\n' => literal.push_str("\\n"),
+                    '\t' => literal.push_str("\\t"),
+                    '\r' => literal.push_str("\\r"),
+                    c => literal.push(c),
+                }
+            }
+            literal.push('"');
+            literal
+        }
+        Vobj::Double(value) => format!("{:?}", value),
+        Vobj::Int(value) => value.to_string(),
+        Vobj::Null => "null".to_string(),
+        Vobj::Error(error) => format!("<{}: {}>", error.kind, error.message),
+        Vobj::List(values) => {
+            let values: Vec<String> = values.iter().map(format_value).collect();
+            format!("[{}]", values.join(", "))
+        }
+        Vobj::Range(range) => range.to_string(),
+        Vobj::Iter(_) => "<iterator>".to_string(),
+        Vobj::Bool(value) => value.to_string(),
+    }
+}
+
+pub fn parse_value(text: &str) -> Result<Vobj, String> {
+    parse_nested_value(text, 0)
+}
+
+// A value inside `depth` enclosing list literals.
+fn parse_nested_value(text: &str, depth: usize) -> Result<Vobj, String> {
+    if text.is_empty() {
+        return Err("missing value".to_string());
+    }
+    if let Some(body) = text.strip_prefix('"') {
+        return parse_string(body).map(Vobj::Str);
+    }
+    if let Some(body) = text.strip_prefix('[') {
+        if depth == MAX_LIST_DEPTH {
+            return Err(format!(
+                "list literal nested deeper than {} levels",
+                MAX_LIST_DEPTH
+            ));
+        }
+        return parse_list(body, depth + 1).map(Vobj::List);
+    }
+    if let Some(body) = text.strip_prefix("range(") {
+        return parse_range(body).map(Vobj::Range);
+    }
+    match text {
+        "null" => return Ok(Vobj::Null),
+        "true" => return Ok(Vobj::Bool(true)),
+        "false" => return Ok(Vobj::Bool(false)),
+        _ => {}
+    }
+    if let Ok(value) = text.parse::<i64>() {
+        return Ok(Vobj::Int(value));
+    }
+    match text.parse::<f64>() {
+        Ok(value) => Ok(Vobj::Double(value)),
+        Err(_) => Err(format!("invalid value `{}`", text)),
+    }
+}
+
+// The elements of a `[a, b, ...]` literal at nesting level `level`, `body`
+// being everything after the opening bracket.
+fn parse_list(body: &str, level: usize) -> Result<Vec<Vobj>, String> {
+    let mut values = Vec::new();
+    let mut depth = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut start = 0;
+    for (index, c) in body.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            _ if in_string => {}
+            '[' | '(' => depth += 1,
+            ',' if depth == 0 => {
+                values.push(parse_nested_value(body[start..index].trim(), level)?);
+                start = index + 1;
+            }
+            ']' | ')' if depth > 0 => depth -= 1,
+            ']' => {
+                if !body[index + 1..].trim().is_empty() {
+                    return Err("unexpected characters after list literal".to_string());
+                }
+                let last = body[start..index].trim();
+                if !last.is_empty() || !values.is_empty() {
+                    values.push(parse_nested_value(last, level)?);
+                }
+                return Ok(values);
+            }
+            _ => {}
+        }
+    }
+    Err("unterminated list literal".to_string())
+}
+
+// `range(start, stop)` or `range(start, stop, step)`, `body` being everything
+// after the opening parenthesis.
+fn parse_range(body: &str) -> Result<IntRange, String> {
+    let arguments = body
+        .trim_end()
+        .strip_suffix(')')
+        .ok_or("unterminated range literal")?;
+    let bounds = arguments
+        .split(',')
+        .map(|bound| {
+            bound
+                .trim()
+                .parse::<i64>()
+                .map_err(|_| format!("invalid range bound `{}`", bound.trim()))
+        })
+        .collect::<Result<Vec<i64>, String>>()?;
+    let range = match bounds[..] {
+        [start, stop] => IntRange::new(start, stop, 1),
+        [start, stop, step] => IntRange::new(start, stop, step),
+        _ => return Err("a range takes a start, a stop and an optional step".to_string()),
+    };
+    range.map_err(|error| error.to_string())
+}
+
+fn parse_string(body: &str) -> Result<String, String> {
+    let mut value = String::new();
+    let mut chars = body.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                if chars.as_str().trim().is_empty() {
+                    return Ok(value);
+                }
+                return Err("unexpected characters after string literal".to_string());
+            }
+            '\\' => match chars.next() {
+                Some('"') => value.push('"'),
+                Some('\\') => value.push('\\'),
+                Some('n') => value.push('\n'),
+                Some('t') => value.push('\t'),
+                Some('r') => value.push('\r'),
+                Some(other) => return Err(format!("unknown escape `\\{}`", other)),
+                None => break,
+            },
+            c => value.push(c),
+        }
+    }
+    Err("unterminated string literal".to_string())
+}
+
+fn parse_operand(
+    kind: OperandKind,
+    text: &str,
+    labels: &HashMap<String, usize>,
+) -> Result<Operand, String> {
+    match kind {
+        OperandKind::None if text.is_empty() => Ok(Operand::None),
+        OperandKind::None => Err(format!("unexpected operand `{}`", text)),
+        OperandKind::Value => parse_value(text).map(Operand::Value),
+        OperandKind::Index if text.is_empty() => Err("missing branch target".to_string()),
+        OperandKind::Index => {
+            if let Ok(index) = text.parse::<usize>() {
+                Ok(Operand::Index(index))
+            } else if let Some(index) = labels.get(text) {
+                Ok(Operand::Index(*index))
+            } else {
+                Err(format!("unknown label `{}`", text))
+            }
+        }
+        OperandKind::Name if is_identifier(text) => Ok(Operand::Name(text.to_string())),
+        OperandKind::Name if text.is_empty() => Err("missing name".to_string()),
+        OperandKind::Name => Err(format!("invalid name `{}`", text)),
+    }
+}
+
+fn strip_comment(line: &str) -> &str {
+    let mut in_string = false;
+    let mut escaped = false;
+    for (index, c) in line.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            ';' if !in_string => return &line[..index],
+            _ => {}
+        }
+    }
+    line
+}
+
+fn split_label(line: &str) -> Option<(&str, &str)> {
+    let (label, rest) = line.split_once(':')?;
+    if is_identifier(label) {
+        Some((label, rest.trim()))
+    } else {
+        None
+    }
+}
+
+fn is_identifier(text: &str) -> bool {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) if first.is_ascii_alphabetic() || first == '_' => {
+            chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        }
+        _ => false,
+    }
+}
@@ -0,0 +1,160 @@
+use std::fs;
+use std::io::{self, BufRead, Write};
+
+use vm::asm;
+use vm::diagnostic::Diagnostic;
+use vm::instruction::{Instruction, Operand};
+use vm::vm::{Snapshot, Vm};
+
+const HELP: &str = "enter one instruction per line, e.g. `load 2` or `add`
+  :program       show the program entered so far
+  :undo          remove the last entered instruction (or loaded file)
+  :reset         start over with an empty vm
+  :load <file>   assemble a .zt file and execute it
+  :help          show this message
+  :quit          exit";
+
+const REPL_SOURCE: &str = "<repl>";
+
+// keeps an accidental infinite loop from hanging the session
+const ENTRY_FUEL: u64 = 10_000_000;
+
+fn main() {
+    let mut vm = Vm::load(Vec::new());
+    // what the vm looked like before each entry was executed, so it can be
+    // undone
+    let mut history: Vec<Snapshot> = Vec::new();
+    let mut line_number: u32 = 0;
+    // every entered instruction line, so diagnostics can quote them
+    let mut transcript = String::new();
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+
+    println!("vm repl, :help for commands");
+    loop {
+        print!("> ");
+        io::stdout().flush().expect("failed to flush stdout");
+        let line = match lines.next() {
+            Some(Ok(line)) => line,
+            Some(Err(error)) => {
+                eprintln!("error: {}", error);
+                break;
+            }
+            None => break,
+        };
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        if let Some(command) = line.strip_prefix(':') {
+            let (command, argument) = match command.split_once(char::is_whitespace) {
+                Some((command, argument)) => (command, argument.trim()),
+                None => (command, ""),
+            };
+            match command {
+                "quit" | "q" => break,
+                "help" | "h" => println!("{}", HELP),
+                "program" | "p" => print!("{}", asm::disassemble(vm.code())),
+                "reset" => {
+                    vm = Vm::load(Vec::new());
+                    history.clear();
+                    line_number = 0;
+                    transcript.clear();
+                }
+                "undo" | "u" => match history.pop() {
+                    Some(snapshot) => {
+                        vm.rewind(snapshot);
+                        print_stack(&vm);
+                    }
+                    None => println!("nothing to undo"),
+                },
+                "load" | "l" => load_file(&mut vm, &mut history, &transcript, argument),
+                _ => println!("unknown command `:{}`, :help for commands", command),
+            }
+            continue;
+        }
+
+        line_number += 1;
+        transcript.push_str(line);
+        transcript.push('\n');
+        match asm::assemble_from(line, REPL_SOURCE, line_number) {
+            Ok(code) => {
+                let base = vm.code().len();
+                execute(&mut vm, &mut history, &transcript, relocate(code, base));
+            }
+            Err(error) => print!("{}", Diagnostic::from(&error).render(&transcript)),
+        }
+    }
+}
+
+fn load_file(vm: &mut Vm, history: &mut Vec<Snapshot>, transcript: &str, path: &str) {
+    if path.is_empty() {
+        println!("usage: :load <file>");
+        return;
+    }
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(error) => {
+            println!("error: {}: {}", path, error);
+            return;
+        }
+    };
+    match asm::assemble(&text, path) {
+        Ok(code) => {
+            let base = vm.code().len();
+            execute(vm, history, transcript, relocate(code, base));
+        }
+        Err(error) => print!("{}", Diagnostic::from(&error).render(&text)),
+    }
+}
+
+// Branch targets in a file or typed line are relative to its first
+// instruction.
+fn relocate(code: Vec<Instruction>, base: usize) -> Vec<Instruction> {
+    code.into_iter()
+        .map(|instruction| match instruction.operand() {
+            Operand::Index(index) => Instruction::from_parts(
+                instruction.name(),
+                Operand::Index(index + base),
+                instruction.src().clone(),
+            )
+            .expect("relocated branch is valid"),
+            _ => instruction,
+        })
+        .collect()
+}
+
+fn execute(vm: &mut Vm, history: &mut Vec<Snapshot>, transcript: &str, code: Vec<Instruction>) {
+    let snapshot = vm.snapshot();
+    vm.append(code);
+    vm.set_fuel(Some(ENTRY_FUEL));
+    match vm.run() {
+        Ok(()) => {
+            history.push(snapshot);
+            print_stack(vm);
+        }
+        Err(error) => {
+            let diagnostic = Diagnostic::from(&*error);
+            print!(
+                "{}",
+                diagnostic.render(&source_text(&diagnostic, transcript))
+            );
+            vm.rewind(snapshot);
+        }
+    }
+}
+
+// The failing instruction may come from a file loaded earlier rather than
+// from what was typed.
+fn source_text(diagnostic: &Diagnostic, transcript: &str) -> String {
+    match diagnostic.src.source_file() {
+        REPL_SOURCE => transcript.to_string(),
+        path => fs::read_to_string(path).unwrap_or_default(),
+    }
+}
+
+fn print_stack(vm: &Vm) {
+    let values: Vec<String> = vm.dump_mem().iter().map(asm::format_value).collect();
+    println!("[{}]", values.join(", "));
+}
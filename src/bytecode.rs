@@ -0,0 +1,315 @@
+use std::sync::Arc;
+
+use super::err::Source;
+use super::instruction::{Instruction, Operand};
+use super::vobj::{ErrorValue, IntRange, IterState, Vobj, MAX_LIST_DEPTH};
+
+pub const MAGIC: &[u8; 4] = b"ZTBC";
+pub const VERSION: u8 = 2;
+
+const OPERAND_NONE: u8 = 0;
+const OPERAND_INDEX: u8 = 1;
+const OPERAND_VALUE: u8 = 2;
+const OPERAND_NAME: u8 = 3;
+
+const VALUE_NULL: u8 = 0;
+const VALUE_INT: u8 = 1;
+const VALUE_DOUBLE: u8 = 2;
+const VALUE_STR: u8 = 3;
+const VALUE_ERROR: u8 = 4;
+const VALUE_BOOL: u8 = 5;
+const VALUE_LIST: u8 = 6;
+const VALUE_ITER: u8 = 7;
+const VALUE_RANGE: u8 = 8;
+
+const ITER_COUNT: u8 = 0;
+const ITER_CHARS: u8 = 1;
+const ITER_ITEMS: u8 = 2;
+const ITER_RANGE: u8 = 3;
+
+pub fn is_bytecode(bytes: &[u8]) -> bool {
+    bytes.starts_with(MAGIC)
+}
+
+/// Serializes a program, including the source location of every instruction.
+pub fn encode(code: &[Instruction]) -> Vec<u8> {
+    let mut files: Vec<&str> = Vec::new();
+    for instruction in code {
+        let file = instruction.src().source_file();
+        if !files.contains(&file) {
+            files.push(file);
+        }
+    }
+
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(MAGIC);
+    bytes.push(VERSION);
+    write_u32(&mut bytes, files.len() as u32);
+    for file in &files {
+        write_str(&mut bytes, file);
+    }
+    write_u32(&mut bytes, code.len() as u32);
+    for instruction in code {
+        let src = instruction.src();
+        let file_index = files
+            .iter()
+            .position(|file| *file == src.source_file())
+            .expect("source file missing from table");
+        write_str(&mut bytes, instruction.name());
+        write_u32(&mut bytes, file_index as u32);
+        write_u32(&mut bytes, src.line_number());
+        write_u32(&mut bytes, src.column().unwrap_or(0));
+        write_u32(&mut bytes, src.span().map_or(0, |span| span.len() as u32));
+        match instruction.operand() {
+            Operand::None => bytes.push(OPERAND_NONE),
+            Operand::Index(index) => {
+                bytes.push(OPERAND_INDEX);
+                bytes.extend_from_slice(&(index as u64).to_le_bytes());
+            }
+            Operand::Value(value) => {
+                bytes.push(OPERAND_VALUE);
+                write_value(&mut bytes, &value);
+            }
+            Operand::Name(name) => {
+                bytes.push(OPERAND_NAME);
+                write_str(&mut bytes, &name);
+            }
+        }
+    }
+    bytes
+}
+
+/// Deserializes a program produced by [`encode`].
+pub fn decode(bytes: &[u8]) -> Result<Vec<Instruction>, String> {
+    let mut reader = Reader { bytes, pos: 0 };
+    if reader.take(MAGIC.len())? != MAGIC {
+        return Err("not a bytecode file".to_string());
+    }
+    let version = reader.u8()?;
+    if version != VERSION {
+        return Err(format!("unsupported bytecode version {}", version));
+    }
+
+    let file_count = reader.u32()? as usize;
+    let mut files = Vec::new();
+    for _ in 0..file_count {
+        files.push(Arc::<str>::from(reader.str()?));
+    }
+
+    let count = reader.u32()? as usize;
+    let mut code = Vec::new();
+    for _ in 0..count {
+        let name = reader.str()?;
+        let file_index = reader.u32()? as usize;
+        let line_number = reader.u32()?;
+        let column = reader.u32()?;
+        let len = reader.u32()?;
+        let file = files
+            .get(file_index)
+            .ok_or_else(|| format!("invalid source file index {}", file_index))?;
+        let operand = match reader.u8()? {
+            OPERAND_NONE => Operand::None,
+            OPERAND_INDEX => Operand::Index(reader.u64()? as usize),
+            OPERAND_VALUE => Operand::Value(reader.value(0)?),
+            OPERAND_NAME => Operand::Name(reader.str()?.to_string()),
+            tag => return Err(format!("invalid operand tag {}", tag)),
+        };
+        code.push(Instruction::from_parts(
+            name,
+            operand,
+            Source::with_span(Arc::clone(file), line_number, column, len),
+        )?);
+    }
+    if reader.pos != bytes.len() {
+        return Err("trailing bytes after program".to_string());
+    }
+    Ok(code)
+}
+
+fn write_u32(bytes: &mut Vec<u8>, value: u32) {
+    bytes.extend_from_slice(&value.to_le_bytes());
+}
+
+fn write_str(bytes: &mut Vec<u8>, value: &str) {
+    write_u32(bytes, value.len() as u32);
+    bytes.extend_from_slice(value.as_bytes());
+}
+
+fn write_value(bytes: &mut Vec<u8>, value: &Vobj) {
+    match value {
+        Vobj::Null => bytes.push(VALUE_NULL),
+        Vobj::Int(value) => {
+            bytes.push(VALUE_INT);
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        Vobj::Double(value) => {
+            bytes.push(VALUE_DOUBLE);
+            bytes.extend_from_slice(&value.to_bits().to_le_bytes());
+        }
+        Vobj::Str(value) => {
+            bytes.push(VALUE_STR);
+            write_str(bytes, value);
+        }
+        Vobj::Error(error) => {
+            bytes.push(VALUE_ERROR);
+            write_str(bytes, &error.kind);
+            write_str(bytes, &error.message);
+        }
+        Vobj::Bool(value) => {
+            bytes.push(VALUE_BOOL);
+            bytes.push(*value as u8);
+        }
+        Vobj::List(values) => {
+            bytes.push(VALUE_LIST);
+            write_u32(bytes, values.len() as u32);
+            for value in values {
+                write_value(bytes, value);
+            }
+        }
+        Vobj::Range(range) => {
+            bytes.push(VALUE_RANGE);
+            write_range(bytes, range);
+        }
+        Vobj::Iter(state) => {
+            bytes.push(VALUE_ITER);
+            match &**state {
+                IterState::Count { next, end } => {
+                    bytes.push(ITER_COUNT);
+                    bytes.extend_from_slice(&next.to_le_bytes());
+                    bytes.extend_from_slice(&end.to_le_bytes());
+                }
+                IterState::Chars { text, offset } => {
+                    bytes.push(ITER_CHARS);
+                    write_str(bytes, text);
+                    write_u32(bytes, *offset as u32);
+                }
+                IterState::Items { values, index } => {
+                    bytes.push(ITER_ITEMS);
+                    write_value(bytes, &Vobj::List(values.clone()));
+                    write_u32(bytes, *index as u32);
+                }
+                IterState::Range { range, index } => {
+                    bytes.push(ITER_RANGE);
+                    write_range(bytes, range);
+                    bytes.extend_from_slice(&index.to_le_bytes());
+                }
+            }
+        }
+    }
+}
+
+fn write_range(bytes: &mut Vec<u8>, range: &IntRange) {
+    for bound in [range.start(), range.stop(), range.step()] {
+        bytes.extend_from_slice(&bound.to_le_bytes());
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or_else(|| "unexpected end of bytecode".to_string())?;
+        let slice = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        let mut buf = [0; 4];
+        buf.copy_from_slice(self.take(4)?);
+        Ok(u32::from_le_bytes(buf))
+    }
+
+    fn u64(&mut self) -> Result<u64, String> {
+        let mut buf = [0; 8];
+        buf.copy_from_slice(self.take(8)?);
+        Ok(u64::from_le_bytes(buf))
+    }
+
+    fn str(&mut self) -> Result<&'a str, String> {
+        let len = self.u32()? as usize;
+        std::str::from_utf8(self.take(len)?).map_err(|_| "invalid utf-8 in bytecode".to_string())
+    }
+
+    // A value inside `depth` enclosing lists.
+    fn value(&mut self, depth: usize) -> Result<Vobj, String> {
+        match self.u8()? {
+            VALUE_NULL => Ok(Vobj::Null),
+            VALUE_INT => Ok(Vobj::Int(self.u64()? as i64)),
+            VALUE_DOUBLE => Ok(Vobj::Double(f64::from_bits(self.u64()?))),
+            VALUE_STR => Ok(Vobj::Str(self.str()?.to_string())),
+            VALUE_ERROR => Ok(Vobj::Error(Box::new(ErrorValue {
+                kind: self.str()?.to_string(),
+                message: self.str()?.to_string(),
+            }))),
+            VALUE_BOOL => match self.u8()? {
+                0 => Ok(Vobj::Bool(false)),
+                1 => Ok(Vobj::Bool(true)),
+                byte => Err(format!("invalid bool {}", byte)),
+            },
+            VALUE_LIST => {
+                if depth == MAX_LIST_DEPTH {
+                    return Err(format!(
+                        "lists nested deeper than {} levels",
+                        MAX_LIST_DEPTH
+                    ));
+                }
+                let len = self.u32()?;
+                (0..len)
+                    .map(|_| self.value(depth + 1))
+                    .collect::<Result<_, _>>()
+                    .map(Vobj::List)
+            }
+            VALUE_RANGE => Ok(Vobj::Range(self.range()?)),
+            VALUE_ITER => Ok(Vobj::Iter(Box::new(self.iter_state(depth)?))),
+            tag => Err(format!("invalid value tag {}", tag)),
+        }
+    }
+
+    fn range(&mut self) -> Result<IntRange, String> {
+        let start = self.u64()? as i64;
+        let stop = self.u64()? as i64;
+        let step = self.u64()? as i64;
+        IntRange::new(start, stop, step).map_err(|error| error.to_string())
+    }
+
+    fn iter_state(&mut self, depth: usize) -> Result<IterState, String> {
+        match self.u8()? {
+            ITER_COUNT => Ok(IterState::Count {
+                next: self.u64()? as i64,
+                end: self.u64()? as i64,
+            }),
+            ITER_CHARS => {
+                let text = self.str()?.to_string();
+                let offset = self.u32()? as usize;
+                if !text.is_char_boundary(offset) {
+                    return Err(format!("invalid iterator offset {}", offset));
+                }
+                Ok(IterState::Chars { text, offset })
+            }
+            ITER_ITEMS => match self.value(depth)? {
+                Vobj::List(values) => Ok(IterState::Items {
+                    values,
+                    index: self.u32()? as usize,
+                }),
+                _ => Err("invalid iterator elements".to_string()),
+            },
+            ITER_RANGE => Ok(IterState::Range {
+                range: self.range()?,
+                index: self.u64()?,
+            }),
+            tag => Err(format!("invalid iterator tag {}", tag)),
+        }
+    }
+}
@@ -0,0 +1,134 @@
+use std::fmt;
+
+use super::asm::{self, AsmError};
+use super::err::{Backtrace, RuntimeError, Source};
+use super::vobj::Vobj;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiagnosticKind {
+    Assembly,
+    Verification,
+    Runtime,
+}
+
+impl fmt::Display for DiagnosticKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DiagnosticKind::Assembly => write!(f, "assembly error"),
+            DiagnosticKind::Verification => write!(f, "verification error"),
+            DiagnosticKind::Runtime => write!(f, "runtime error"),
+        }
+    }
+}
+
+/// An error pinned to a source location, with whatever vm state was
+/// available when it happened.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub kind: DiagnosticKind,
+    pub message: String,
+    pub src: Source,
+    pub stack: Option<Vec<Vobj>>,
+    pub backtrace: Backtrace,
+}
+
+impl Diagnostic {
+    pub fn new(kind: DiagnosticKind, message: impl Into<String>, src: Source) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+            src,
+            stack: None,
+            backtrace: Backtrace::default(),
+        }
+    }
+
+    /// Renders the diagnostic, quoting the offending line of `text` when it
+    /// is the source the location refers to.
+    pub fn render(&self, text: &str) -> String {
+        let line_number = self.src.line_number();
+        let line = (line_number as usize)
+            .checked_sub(1)
+            .and_then(|index| text.lines().nth(index));
+        let gutter = " ".repeat(line_number.to_string().len());
+
+        let mut out = format!("{}: {}\n", self.kind, self.message);
+        out.push_str(&format!("{}--> {}\n", gutter, self.src));
+        if let Some(line) = line {
+            out.push_str(&format!("{} |\n", gutter));
+            out.push_str(&format!("{} | {}\n", line_number, line));
+            out.push_str(&format!("{} | {}\n", gutter, underline(line, &self.src)));
+        }
+        if let Some(stack) = &self.stack {
+            let values: Vec<String> = stack.iter().map(asm::format_value).collect();
+            out.push_str(&format!("{} = stack: [{}]\n", gutter, values.join(", ")));
+        }
+        if !self.backtrace.frames.is_empty() {
+            out.push_str(&format!("{} = backtrace:\n", gutter));
+            for (depth, frame) in self.backtrace.frames.iter().enumerate() {
+                out.push_str(&format!(
+                    "{}     {}: {}, called from {} ({})\n",
+                    gutter, depth, frame.name, frame.call_pc, frame.src
+                ));
+            }
+        }
+        if !self.backtrace.branches.is_empty() {
+            out.push_str(&format!("{} = recent branches:\n", gutter));
+            for branch in self.backtrace.branches.iter() {
+                out.push_str(&format!(
+                    "{}     {} -> {} ({})\n",
+                    gutter, branch.pc, branch.target, branch.src
+                ));
+            }
+        }
+        out
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {} ({})", self.kind, self.message, self.src)
+    }
+}
+
+impl From<&RuntimeError> for Diagnostic {
+    fn from(error: &RuntimeError) -> Self {
+        Self {
+            kind: DiagnosticKind::Runtime,
+            message: error.error.to_string(),
+            src: error.src.clone(),
+            stack: Some(error.stack.clone()),
+            backtrace: error.backtrace.clone(),
+        }
+    }
+}
+
+impl From<&AsmError> for Diagnostic {
+    fn from(error: &AsmError) -> Self {
+        Self::new(
+            DiagnosticKind::Assembly,
+            error.message.clone(),
+            error.src.clone(),
+        )
+    }
+}
+
+// Carets under the span, or under the whole line when there is none. Tabs
+// are kept so the carets line up however the terminal expands them.
+fn underline(line: &str, src: &Source) -> String {
+    let (start, len) = match src.span() {
+        Some(span) => (span.start as usize - 1, span.len().max(1)),
+        None => {
+            let trimmed = line.trim_start();
+            let start = line.chars().count() - trimmed.chars().count();
+            (start, trimmed.trim_end().chars().count().max(1))
+        }
+    };
+    let mut marks: String = line
+        .chars()
+        .take(start)
+        .map(|c| if c == '\t' { '\t' } else { ' ' })
+        .collect();
+    marks.push_str(&"^".repeat(len));
+    marks
+}
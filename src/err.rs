@@ -1,37 +1,431 @@
 use std::fmt;
-#[derive(Debug, Clone, PartialEq)]
-pub struct Source<'a> {
-    source_file: &'a str,
+use std::io;
+use std::ops::Range;
+use std::sync::Arc;
+
+use super::vobj::{Vobj, VobjKind};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Source {
+    source_file: Arc<str>,
     line_number: u32,
+    column: u32,
+    len: u32,
 }
 
-impl<'a> Source<'a> {
-    pub fn new(source_file: &'a str, line_number: u32) -> Self {
+impl Source {
+    /// `source_file` may be a `&str`, a `String` or an `Arc<str>` shared
+    /// between all the locations of one file.
+    pub fn new(source_file: impl Into<Arc<str>>, line_number: u32) -> Self {
+        Self::with_span(source_file, line_number, 0, 0)
+    }
+
+    /// A location covering `len` characters from the 1-based `column`; a
+    /// column of 0 means the whole line.
+    pub fn with_span(
+        source_file: impl Into<Arc<str>>,
+        line_number: u32,
+        column: u32,
+        len: u32,
+    ) -> Self {
         Self {
-            source_file,
+            source_file: source_file.into(),
             line_number,
+            column,
+            len,
         }
     }
+
+    pub fn source_file(&self) -> &str {
+        &self.source_file
+    }
+
+    pub fn shared_source_file(&self) -> &Arc<str> {
+        &self.source_file
+    }
+
+    pub fn line_number(&self) -> u32 {
+        self.line_number
+    }
+
+    pub fn column(&self) -> Option<u32> {
+        (self.column > 0).then_some(self.column)
+    }
+
+    /// The covered columns, 1-based and end exclusive.
+    pub fn span(&self) -> Option<Range<u32>> {
+        self.column().map(|column| column..column + self.len)
+    }
 }
 
-impl<'a> fmt::Display for Source<'a> {
+impl fmt::Display for Source {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         // Write the formatted string to the formatter
-        write!(f, "{}:{}", self.source_file, self.line_number)
+        match self.column() {
+            Some(column) => write!(f, "{}:{}:{}", self.source_file, self.line_number, column),
+            None => write!(f, "{}:{}", self.source_file, self.line_number),
+        }
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Limit {
+    StackDepth(usize),
+    HeapBytes(usize),
+    CallDepth(usize),
+}
+
+impl fmt::Display for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Limit::StackDepth(max) => write!(f, "operand stack is limited to {} values", max),
+            Limit::HeapBytes(max) => write!(f, "heap is limited to {} bytes", max),
+            Limit::CallDepth(max) => write!(f, "call depth is limited to {} frames", max),
+        }
+    }
+}
+
+/// Which operand of an operation an error is about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Position {
+    Left,
+    Right,
+    Only,
+    /// The 1-based argument of a native function.
+    Argument(usize),
+    /// The 0-based element of a list operand.
+    Element(usize),
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Position::Left => write!(f, "left operand"),
+            Position::Right => write!(f, "right operand"),
+            Position::Only => write!(f, "operand"),
+            Position::Argument(index) => write!(f, "argument {}", index),
+            Position::Element(index) => write!(f, "element {}", index),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
 pub enum VMError {
     IncorrectArgumentErr(String),
     DivisionByZeroErr,
+    LimitExceededErr(Limit),
+    /// An operand of the wrong kind, named by operation and position.
+    TypeErr {
+        operation: &'static str,
+        position: Position,
+        expected: Vec<VobjKind>,
+        actual: VobjKind,
+    },
+    StackUnderflowErr(usize),
+    InvalidBranchErr(usize),
+    /// Integer arithmetic that does not fit in an `i64`, named by operation.
+    OverflowErr(&'static str),
+    /// A shift by a negative amount or by 64 bits or more.
+    InvalidShiftErr(i64),
+    IndexOutOfRangeErr {
+        index: i64,
+        len: usize,
+    },
+    UnknownNativeErr(String),
+    /// A value that has no representation as `target`, like the string
+    /// `"abc"` as an `Int`.
+    ConversionErr {
+        value: Vobj,
+        target: &'static str,
+    },
+    UndefinedGlobalErr(String),
+    ReturnOutsideCallErr,
+    UnmatchedTryEndErr,
+    IoErr(Arc<io::Error>),
+    OutOfFuelErr,
+    DeadlineExceededErr,
+    /// A value raised by `Throw`.
+    ThrownErr(Vobj),
 }
 
+impl VMError {
+    /// The name a caught error carries as its kind.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            VMError::IncorrectArgumentErr(_) => "IncorrectArgument",
+            VMError::DivisionByZeroErr => "DivisionByZero",
+            VMError::LimitExceededErr(_) => "LimitExceeded",
+            VMError::TypeErr { .. } => "Type",
+            VMError::StackUnderflowErr(_) => "StackUnderflow",
+            VMError::InvalidBranchErr(_) => "InvalidBranch",
+            VMError::OverflowErr(_) => "Overflow",
+            VMError::InvalidShiftErr(_) => "InvalidShift",
+            VMError::IndexOutOfRangeErr { .. } => "IndexOutOfRange",
+            VMError::UnknownNativeErr(_) => "UnknownNative",
+            VMError::ConversionErr { .. } => "Conversion",
+            VMError::UndefinedGlobalErr(_) => "UndefinedGlobal",
+            VMError::ReturnOutsideCallErr => "ReturnOutsideCall",
+            VMError::UnmatchedTryEndErr => "UnmatchedTryEnd",
+            VMError::IoErr(_) => "Io",
+            VMError::OutOfFuelErr => "OutOfFuel",
+            VMError::DeadlineExceededErr => "DeadlineExceeded",
+            VMError::ThrownErr(_) => "Thrown",
+        }
+    }
+}
+
+impl fmt::Display for VMError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VMError::IncorrectArgumentErr(msg) => write!(f, "incorrect argument error: {}", msg),
+            VMError::DivisionByZeroErr => write!(f, "Division by zero error"),
+            VMError::LimitExceededErr(limit) => write!(f, "limit exceeded: {}", limit),
+            VMError::TypeErr {
+                operation,
+                position,
+                expected,
+                actual,
+            } => {
+                let expected: Vec<&str> = expected.iter().map(VobjKind::name).collect();
+                write!(
+                    f,
+                    "{}: {} is {}, expected {}",
+                    operation,
+                    position,
+                    actual,
+                    expected.join(" or ")
+                )
+            }
+            VMError::StackUnderflowErr(1) => write!(f, "at least 1 argument is required"),
+            VMError::StackUnderflowErr(required) => {
+                write!(f, "at least {} arguments are required", required)
+            }
+            VMError::InvalidBranchErr(index) => {
+                write!(f, "invalid instruction index {}: out of bound", index)
+            }
+            VMError::OverflowErr(operation) => write!(f, "integer overflow in {}", operation),
+            VMError::InvalidShiftErr(amount) => {
+                write!(f, "shift amount {} is out of range 0..64", amount)
+            }
+            VMError::IndexOutOfRangeErr { index, len } => {
+                write!(f, "index {} is out of range for length {}", index, len)
+            }
+            VMError::UnknownNativeErr(name) => write!(f, "unknown native function `{}`", name),
+            VMError::ConversionErr {
+                value: Vobj::Str(value),
+                target,
+            } => write!(f, "cannot convert {:?} to {}", value, target),
+            VMError::ConversionErr { value, target } => {
+                write!(f, "cannot convert {} to {}", value, target)
+            }
+            VMError::UndefinedGlobalErr(name) => write!(f, "undefined global `{}`", name),
+            VMError::ReturnOutsideCallErr => write!(f, "return outside of a call"),
+            VMError::UnmatchedTryEndErr => write!(f, "try end without a matching try start"),
+            VMError::IoErr(error) => write!(f, "i/o error: {}", error),
+            VMError::OutOfFuelErr => write!(f, "out of fuel"),
+            VMError::DeadlineExceededErr => write!(f, "deadline exceeded"),
+            VMError::ThrownErr(value) => write!(f, "uncaught exception: {}", value),
+        }
+    }
+}
+
+impl std::error::Error for VMError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            VMError::IoErr(error) => Some(error.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+// io::Error has no equality, so two are equal when they read the same.
+impl PartialEq for VMError {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (VMError::IncorrectArgumentErr(a), VMError::IncorrectArgumentErr(b)) => a == b,
+            (VMError::LimitExceededErr(a), VMError::LimitExceededErr(b)) => a == b,
+            (
+                VMError::TypeErr {
+                    operation,
+                    position,
+                    expected,
+                    actual,
+                },
+                VMError::TypeErr {
+                    operation: other_operation,
+                    position: other_position,
+                    expected: other_expected,
+                    actual: other_actual,
+                },
+            ) => {
+                operation == other_operation
+                    && position == other_position
+                    && expected == other_expected
+                    && actual == other_actual
+            }
+            (VMError::StackUnderflowErr(a), VMError::StackUnderflowErr(b)) => a == b,
+            (VMError::InvalidBranchErr(a), VMError::InvalidBranchErr(b)) => a == b,
+            (VMError::OverflowErr(a), VMError::OverflowErr(b)) => a == b,
+            (VMError::InvalidShiftErr(a), VMError::InvalidShiftErr(b)) => a == b,
+            (
+                VMError::IndexOutOfRangeErr { index, len },
+                VMError::IndexOutOfRangeErr {
+                    index: other_index,
+                    len: other_len,
+                },
+            ) => index == other_index && len == other_len,
+            (VMError::UnknownNativeErr(a), VMError::UnknownNativeErr(b)) => a == b,
+            (
+                VMError::ConversionErr { value, target },
+                VMError::ConversionErr {
+                    value: other_value,
+                    target: other_target,
+                },
+            ) => value == other_value && target == other_target,
+            (VMError::UndefinedGlobalErr(a), VMError::UndefinedGlobalErr(b)) => a == b,
+            (VMError::IoErr(a), VMError::IoErr(b)) => {
+                a.kind() == b.kind() && a.to_string() == b.to_string()
+            }
+            (VMError::ThrownErr(a), VMError::ThrownErr(b)) => a == b,
+            _ => std::mem::discriminant(self) == std::mem::discriminant(other),
+        }
+    }
+}
+
+impl From<io::Error> for VMError {
+    fn from(error: io::Error) -> Self {
+        VMError::IoErr(Arc::new(error))
+    }
+}
+
+#[allow(clippy::from_over_into)]
 impl Into<String> for VMError {
     fn into(self) -> String {
+        self.to_string()
+    }
+}
+
+/// A taken branch, call or return.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BranchRecord {
+    pub pc: usize,
+    pub target: usize,
+    pub src: Source,
+}
+
+/// One active call, described by where it was made from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BacktraceFrame {
+    pub name: String,
+    pub call_pc: usize,
+    pub src: Source,
+}
+
+/// How control reached a failing instruction.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Backtrace {
+    /// Innermost call first.
+    pub frames: Vec<BacktraceFrame>,
+    /// The most recent control transfers, oldest first.
+    pub branches: Vec<BranchRecord>,
+}
+
+impl Backtrace {
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty() && self.branches.is_empty()
+    }
+}
+
+impl fmt::Display for Backtrace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !self.frames.is_empty() {
+            writeln!(f, "frames:")?;
+            for (depth, frame) in self.frames.iter().enumerate() {
+                writeln!(
+                    f,
+                    "  {}: {}, called from {} ({})",
+                    depth, frame.name, frame.call_pc, frame.src
+                )?;
+            }
+        }
+        if !self.branches.is_empty() {
+            writeln!(f, "recent branches:")?;
+            for branch in self.branches.iter() {
+                writeln!(f, "  {} -> {} ({})", branch.pc, branch.target, branch.src)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// An error that halted [`crate::vm::Vm::run`], with the vm state at the
+/// failing instruction.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuntimeError {
+    pub error: VMError,
+    pub pc: usize,
+    pub instruction: String,
+    pub src: Source,
+    pub stack: Vec<Vobj>,
+    pub backtrace: Backtrace,
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} -> {} {} ({})",
+            self.error, self.pc, self.instruction, self.src
+        )
+    }
+}
+
+impl std::error::Error for RuntimeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+impl From<RuntimeError> for String {
+    fn from(error: RuntimeError) -> Self {
+        error.to_string()
+    }
+}
+
+/// A [`crate::vm::VmBuilder`] setting that no vm can run with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigError {
+    ZeroStackDepth,
+    ZeroCallDepth,
+    /// The initial stack holds more values than the stack depth allows.
+    StackTooDeep {
+        len: usize,
+        max: usize,
+    },
+    /// The initial stack holds more bytes than the heap limit allows.
+    HeapTooLarge {
+        bytes: usize,
+        max: usize,
+    },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            VMError::IncorrectArgumentErr(msg) => format!("incorrect argument error: {}", msg),
-            VMError::DivisionByZeroErr => "Division by zero error".to_string(),
+            ConfigError::ZeroStackDepth => write!(f, "max stack depth must be at least 1"),
+            ConfigError::ZeroCallDepth => write!(f, "max call depth must be at least 1"),
+            ConfigError::StackTooDeep { len, max } => write!(
+                f,
+                "initial stack holds {} values but max stack depth is {}",
+                len, max
+            ),
+            ConfigError::HeapTooLarge { bytes, max } => write!(
+                f,
+                "initial stack holds {} bytes but max heap size is {}",
+                bytes, max
+            ),
         }
     }
 }
+
+impl std::error::Error for ConfigError {}
@@ -1,105 +1,416 @@
 use super::err::Source;
-use super::vobj::Vobj;
+use super::vobj::{Vobj, VobjKind};
 
 #[derive(Debug, Clone, PartialEq)]
-pub enum Instruction<'a> {
+pub enum Operand {
+    None,
+    Value(Vobj),
+    Index(usize),
+    Name(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OperandKind {
+    None,
+    Value,
+    Index,
+    Name,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Instruction {
     Nop {
         str_repr: String,
-        src: Source<'a>,
+        src: Source,
     },
 
     Load {
         value:Vobj,
         str_repr: String,
-        src: Source<'a>,
+        src: Source,
     },
 
     Add {
         str_repr: String,
-        src: Source<'a>,
+        src: Source,
     },
 
     Sub {
         
         str_repr: String,
-        src: Source<'a>,
+        src: Source,
     },
 
     Mul {
         
         str_repr: String,
-        src: Source<'a>,
+        src: Source,
     },
 
     Div {
         
         str_repr: String,
-        src: Source<'a>,
+        src: Source,
     },
 
     Br {
         br_index: usize,
         str_repr: String,
-        src: Source<'a>,
+        src: Source,
     },
 
     Beq {
         
         br_index: usize,
         str_repr: String,
-        src: Source<'a>,
+        src: Source,
     },
 
     Bnq {
         
         br_index: usize,
         str_repr: String,
-        src: Source<'a>,
+        src: Source,
     },
 
     Bg {
         
         br_index: usize,
         str_repr: String,
-        src: Source<'a>,
+        src: Source,
     },
 
     Bge {
         br_index: usize,
         str_repr: String,
-        src: Source<'a>,
+        src: Source,
     },
 
     Blt {
         
         br_index: usize,
         str_repr: String,
-        src: Source<'a>,
+        src: Source,
     },
 
     Ble {
         
         br_index: usize,
         str_repr: String,
-        src: Source<'a>,
+        src: Source,
     },
     Bgt {
         
         br_index: usize,
         str_repr: String,
-        src: Source<'a>,
+        src: Source,
+    },
+
+    Call {
+        br_index: usize,
+        str_repr: String,
+        src: Source,
+    },
+
+    Ret {
+        str_repr: String,
+        src: Source,
+    },
+
+    Print {
+        str_repr: String,
+        src: Source,
+    },
+
+    GLoad {
+        name: String,
+        str_repr: String,
+        src: Source,
+    },
+
+    GStore {
+        name: String,
+        str_repr: String,
+        src: Source,
+    },
+
+    Throw {
+        str_repr: String,
+        src: Source,
+    },
+
+    TryStart {
+        br_index: usize,
+        str_repr: String,
+        src: Source,
+    },
+
+    TryEnd {
+        str_repr: String,
+        src: Source,
+    },
+
+    Rem {
+        str_repr: String,
+        src: Source,
+    },
+
+    Mod {
+        str_repr: String,
+        src: Source,
+    },
+
+    Neg {
+        str_repr: String,
+        src: Source,
+    },
+
+    Pow {
+        str_repr: String,
+        src: Source,
+    },
+
+    Abs {
+        str_repr: String,
+        src: Source,
+    },
+
+    FloorDiv {
+        str_repr: String,
+        src: Source,
+    },
+
+    TrueDiv {
+        str_repr: String,
+        src: Source,
+    },
+
+    And {
+        str_repr: String,
+        src: Source,
+    },
+
+    Or {
+        str_repr: String,
+        src: Source,
+    },
+
+    Xor {
+        str_repr: String,
+        src: Source,
+    },
+
+    Not {
+        str_repr: String,
+        src: Source,
+    },
+
+    Shl {
+        str_repr: String,
+        src: Source,
+    },
+
+    Sar {
+        str_repr: String,
+        src: Source,
+    },
+
+    Shr {
+        str_repr: String,
+        src: Source,
+    },
+
+    Popcount {
+        str_repr: String,
+        src: Source,
+    },
+
+    Native {
+        name: String,
+        str_repr: String,
+        src: Source,
+    },
+
+    ToInt {
+        str_repr: String,
+        src: Source,
+    },
+
+    ToDouble {
+        str_repr: String,
+        src: Source,
+    },
+
+    ToStr {
+        str_repr: String,
+        src: Source,
+    },
+
+    ToBool {
+        str_repr: String,
+        src: Source,
+    },
+
+    ParseNum {
+        str_repr: String,
+        src: Source,
+    },
+
+    TypeOf {
+        str_repr: String,
+        src: Source,
+    },
+
+    IsInt {
+        str_repr: String,
+        src: Source,
+    },
+
+    IsDouble {
+        str_repr: String,
+        src: Source,
+    },
+
+    IsStr {
+        str_repr: String,
+        src: Source,
+    },
+
+    IsBool {
+        str_repr: String,
+        src: Source,
+    },
+
+    IsNull {
+        str_repr: String,
+        src: Source,
+    },
+
+    IsError {
+        str_repr: String,
+        src: Source,
+    },
+
+    AssertType {
+        kind: VobjKind,
+        str_repr: String,
+        src: Source,
+    },
+
+    Bnull {
+        br_index: usize,
+        str_repr: String,
+        src: Source,
+    },
+
+    Bnnull {
+        br_index: usize,
+        str_repr: String,
+        src: Source,
+    },
+
+    Coalesce {
+        str_repr: String,
+        src: Source,
+    },
+
+    Cmp {
+        str_repr: String,
+        src: Source,
+    },
+
+    MakeList {
+        str_repr: String,
+        src: Source,
+    },
+
+    Len {
+        str_repr: String,
+        src: Source,
+    },
+
+    Get {
+        str_repr: String,
+        src: Source,
+    },
+
+    Sort {
+        str_repr: String,
+        src: Source,
+    },
+
+    SortBy {
+        br_index: usize,
+        str_repr: String,
+        src: Source,
+    },
+
+    Reverse {
+        str_repr: String,
+        src: Source,
+    },
+
+    Contains {
+        str_repr: String,
+        src: Source,
+    },
+
+    IndexOf {
+        str_repr: String,
+        src: Source,
+    },
+
+    Min {
+        str_repr: String,
+        src: Source,
+    },
+
+    Max {
+        str_repr: String,
+        src: Source,
+    },
+
+    Sum {
+        str_repr: String,
+        src: Source,
+    },
+
+    Join {
+        str_repr: String,
+        src: Source,
+    },
+
+    IterNew {
+        str_repr: String,
+        src: Source,
+    },
+
+    IterNext {
+        br_index: usize,
+        str_repr: String,
+        src: Source,
+    },
+
+    Range {
+        str_repr: String,
+        src: Source,
+    },
+
+    RangeStep {
+        str_repr: String,
+        src: Source,
     },
 }
 
-impl<'a> Instruction<'a> {
-    pub fn nop_instruction(src: Source<'a>) -> Self {
-        let str_repr: String = "Load".to_string();
+impl Instruction {
+    pub fn nop_instruction(src: Source) -> Self {
+        let str_repr: String = "Nop".to_string();
         Self::Nop {
             str_repr,
             src,
         }
     }
-    pub fn load_instruction(value:Vobj,src: Source<'a>) -> Self {
+    pub fn load_instruction(value:Vobj,src: Source) -> Self {
         let str_repr: String = "Load".to_string();
         Self::Load {
             value,
@@ -108,7 +419,7 @@ impl<'a> Instruction<'a> {
         }
     }
 
-    pub fn add(src: Source<'a>) -> Self {
+    pub fn add(src: Source) -> Self {
         let str_repr: String = "Add".to_string();
         Self::Add {
             str_repr,
@@ -116,7 +427,7 @@ impl<'a> Instruction<'a> {
         }
     }
 
-    pub fn sub(src: Source<'a>) -> Self {
+    pub fn sub(src: Source) -> Self {
         let str_repr: String = "Sub".to_string();
         Self::Sub {
             str_repr,
@@ -124,7 +435,7 @@ impl<'a> Instruction<'a> {
         }
     }
 
-    pub fn mul(src: Source<'a>) -> Self {
+    pub fn mul(src: Source) -> Self {
         let str_repr: String = "Mul".to_string();
         Self::Mul {
             str_repr,
@@ -132,7 +443,7 @@ impl<'a> Instruction<'a> {
         }
     }
 
-    pub fn div(src: Source<'a>) -> Self {
+    pub fn div(src: Source) -> Self {
         let str_repr: String = "Div".to_string();
         Self::Div {
             str_repr,
@@ -140,7 +451,7 @@ impl<'a> Instruction<'a> {
         }
     }
 
-    pub fn br(br_index: usize, src: Source<'a>) -> Self {
+    pub fn br(br_index: usize, src: Source) -> Self {
         let var1 = br_index.to_string();
         let str_repr: String = format!("Br {}", var1);
         Self::Br {
@@ -150,7 +461,7 @@ impl<'a> Instruction<'a> {
         }
     }
 
-    pub fn beq(br_index: usize, src: Source<'a>) -> Self {
+    pub fn beq(br_index: usize, src: Source) -> Self {
         let var1 = br_index.to_string();
         let str_repr: String = format!("Beq {}", var1);
         Self::Beq {
@@ -160,7 +471,7 @@ impl<'a> Instruction<'a> {
         }
     }
 
-    pub fn bnq(br_index: usize, src: Source<'a>) -> Self {
+    pub fn bnq(br_index: usize, src: Source) -> Self {
         let var1 = br_index.to_string();
         let str_repr: String = format!("Bnq {}", var1);
         Self::Bnq {
@@ -171,7 +482,7 @@ impl<'a> Instruction<'a> {
         }
     }
 
-    pub fn bgt(br_index: usize, src: Source<'a>) -> Self {
+    pub fn bgt(br_index: usize, src: Source) -> Self {
         let var1 = br_index.to_string();
         let str_repr: String = format!("Bgt {}", var1);
         Self::Bg {
@@ -182,7 +493,7 @@ impl<'a> Instruction<'a> {
         }
     }
 
-    pub fn bge(br_index: usize, src: Source<'a>) -> Self {
+    pub fn bge(br_index: usize, src: Source) -> Self {
         let var1 = br_index.to_string();
         let str_repr: String = format!("Bge {}", var1);
         Self::Bge {
@@ -192,7 +503,7 @@ impl<'a> Instruction<'a> {
         }
     }
 
-    pub fn blt(br_index: usize, src: Source<'a>) -> Self {
+    pub fn blt(br_index: usize, src: Source) -> Self {
         let var1 = br_index.to_string();
         let str_repr: String = format!("Blt {}", var1);
         Self::Blt {
@@ -202,7 +513,7 @@ impl<'a> Instruction<'a> {
         }
     }
 
-    pub fn ble(br_index: usize, src: Source<'a>) -> Self {
+    pub fn ble(br_index: usize, src: Source) -> Self {
         let var1 = br_index.to_string();
         let str_repr: String = format!("Ble {}", var1);
         Self::Ble {
@@ -211,4 +522,683 @@ impl<'a> Instruction<'a> {
             src,
         }
     }
+
+    pub fn call(br_index: usize, src: Source) -> Self {
+        let str_repr: String = format!("Call {}", br_index);
+        Self::Call {
+            br_index,
+            str_repr,
+            src,
+        }
+    }
+
+    pub fn ret(src: Source) -> Self {
+        let str_repr: String = "Ret".to_string();
+        Self::Ret { str_repr, src }
+    }
+
+    pub fn print(src: Source) -> Self {
+        let str_repr: String = "Print".to_string();
+        Self::Print { str_repr, src }
+    }
+
+    pub fn gload(name: &str, src: Source) -> Self {
+        let str_repr: String = format!("GLoad {}", name);
+        Self::GLoad {
+            name: name.to_string(),
+            str_repr,
+            src,
+        }
+    }
+
+    pub fn gstore(name: &str, src: Source) -> Self {
+        let str_repr: String = format!("GStore {}", name);
+        Self::GStore {
+            name: name.to_string(),
+            str_repr,
+            src,
+        }
+    }
+
+    pub fn throw(src: Source) -> Self {
+        let str_repr: String = "Throw".to_string();
+        Self::Throw { str_repr, src }
+    }
+
+    pub fn try_start(br_index: usize, src: Source) -> Self {
+        let str_repr: String = format!("TryStart {}", br_index);
+        Self::TryStart {
+            br_index,
+            str_repr,
+            src,
+        }
+    }
+
+    pub fn try_end(src: Source) -> Self {
+        let str_repr: String = "TryEnd".to_string();
+        Self::TryEnd { str_repr, src }
+    }
+
+    pub fn rem(src: Source) -> Self {
+        let str_repr: String = "Rem".to_string();
+        Self::Rem { str_repr, src }
+    }
+
+    pub fn modulo(src: Source) -> Self {
+        let str_repr: String = "Mod".to_string();
+        Self::Mod { str_repr, src }
+    }
+
+    pub fn neg(src: Source) -> Self {
+        let str_repr: String = "Neg".to_string();
+        Self::Neg { str_repr, src }
+    }
+
+    pub fn pow(src: Source) -> Self {
+        let str_repr: String = "Pow".to_string();
+        Self::Pow { str_repr, src }
+    }
+
+    pub fn abs(src: Source) -> Self {
+        let str_repr: String = "Abs".to_string();
+        Self::Abs { str_repr, src }
+    }
+
+    pub fn floor_div(src: Source) -> Self {
+        let str_repr: String = "FloorDiv".to_string();
+        Self::FloorDiv { str_repr, src }
+    }
+
+    pub fn true_div(src: Source) -> Self {
+        let str_repr: String = "TrueDiv".to_string();
+        Self::TrueDiv { str_repr, src }
+    }
+
+    pub fn bit_and(src: Source) -> Self {
+        let str_repr: String = "And".to_string();
+        Self::And { str_repr, src }
+    }
+
+    pub fn bit_or(src: Source) -> Self {
+        let str_repr: String = "Or".to_string();
+        Self::Or { str_repr, src }
+    }
+
+    pub fn bit_xor(src: Source) -> Self {
+        let str_repr: String = "Xor".to_string();
+        Self::Xor { str_repr, src }
+    }
+
+    pub fn bit_not(src: Source) -> Self {
+        let str_repr: String = "Not".to_string();
+        Self::Not { str_repr, src }
+    }
+
+    pub fn shl(src: Source) -> Self {
+        let str_repr: String = "Shl".to_string();
+        Self::Shl { str_repr, src }
+    }
+
+    pub fn sar(src: Source) -> Self {
+        let str_repr: String = "Sar".to_string();
+        Self::Sar { str_repr, src }
+    }
+
+    pub fn shr(src: Source) -> Self {
+        let str_repr: String = "Shr".to_string();
+        Self::Shr { str_repr, src }
+    }
+
+    pub fn popcount(src: Source) -> Self {
+        let str_repr: String = "Popcount".to_string();
+        Self::Popcount { str_repr, src }
+    }
+
+    pub fn native(name: &str, src: Source) -> Self {
+        let str_repr: String = format!("Native {}", name);
+        Self::Native {
+            name: name.to_string(),
+            str_repr,
+            src,
+        }
+    }
+
+    pub fn to_int(src: Source) -> Self {
+        let str_repr: String = "ToInt".to_string();
+        Self::ToInt { str_repr, src }
+    }
+
+    pub fn to_double(src: Source) -> Self {
+        let str_repr: String = "ToDouble".to_string();
+        Self::ToDouble { str_repr, src }
+    }
+
+    pub fn to_str(src: Source) -> Self {
+        let str_repr: String = "ToStr".to_string();
+        Self::ToStr { str_repr, src }
+    }
+
+    pub fn to_bool(src: Source) -> Self {
+        let str_repr: String = "ToBool".to_string();
+        Self::ToBool { str_repr, src }
+    }
+
+    pub fn parse_num(src: Source) -> Self {
+        let str_repr: String = "ParseNum".to_string();
+        Self::ParseNum { str_repr, src }
+    }
+
+    pub fn type_of(src: Source) -> Self {
+        let str_repr: String = "TypeOf".to_string();
+        Self::TypeOf { str_repr, src }
+    }
+
+    pub fn is_int(src: Source) -> Self {
+        let str_repr: String = "IsInt".to_string();
+        Self::IsInt { str_repr, src }
+    }
+
+    pub fn is_double(src: Source) -> Self {
+        let str_repr: String = "IsDouble".to_string();
+        Self::IsDouble { str_repr, src }
+    }
+
+    pub fn is_str(src: Source) -> Self {
+        let str_repr: String = "IsStr".to_string();
+        Self::IsStr { str_repr, src }
+    }
+
+    pub fn is_bool(src: Source) -> Self {
+        let str_repr: String = "IsBool".to_string();
+        Self::IsBool { str_repr, src }
+    }
+
+    pub fn is_null(src: Source) -> Self {
+        let str_repr: String = "IsNull".to_string();
+        Self::IsNull { str_repr, src }
+    }
+
+    pub fn is_error(src: Source) -> Self {
+        let str_repr: String = "IsError".to_string();
+        Self::IsError { str_repr, src }
+    }
+
+    pub fn assert_type(kind: VobjKind, src: Source) -> Self {
+        let str_repr: String = format!("AssertType {}", kind);
+        Self::AssertType {
+            kind,
+            str_repr,
+            src,
+        }
+    }
+
+    pub fn bnull(br_index: usize, src: Source) -> Self {
+        let str_repr: String = format!("Bnull {}", br_index);
+        Self::Bnull {
+            br_index,
+            str_repr,
+            src,
+        }
+    }
+
+    pub fn bnnull(br_index: usize, src: Source) -> Self {
+        let str_repr: String = format!("Bnnull {}", br_index);
+        Self::Bnnull {
+            br_index,
+            str_repr,
+            src,
+        }
+    }
+
+    pub fn coalesce(src: Source) -> Self {
+        let str_repr: String = "Coalesce".to_string();
+        Self::Coalesce { str_repr, src }
+    }
+
+    pub fn cmp(src: Source) -> Self {
+        let str_repr: String = "Cmp".to_string();
+        Self::Cmp { str_repr, src }
+    }
+
+    pub fn make_list(src: Source) -> Self {
+        let str_repr: String = "MakeList".to_string();
+        Self::MakeList { str_repr, src }
+    }
+
+    pub fn len(src: Source) -> Self {
+        let str_repr: String = "Len".to_string();
+        Self::Len { str_repr, src }
+    }
+
+    pub fn get(src: Source) -> Self {
+        let str_repr: String = "Get".to_string();
+        Self::Get { str_repr, src }
+    }
+
+    pub fn sort(src: Source) -> Self {
+        let str_repr: String = "Sort".to_string();
+        Self::Sort { str_repr, src }
+    }
+
+    pub fn sort_by(br_index: usize, src: Source) -> Self {
+        let str_repr: String = format!("SortBy {}", br_index);
+        Self::SortBy {
+            br_index,
+            str_repr,
+            src,
+        }
+    }
+
+    pub fn reverse(src: Source) -> Self {
+        let str_repr: String = "Reverse".to_string();
+        Self::Reverse { str_repr, src }
+    }
+
+    pub fn contains(src: Source) -> Self {
+        let str_repr: String = "Contains".to_string();
+        Self::Contains { str_repr, src }
+    }
+
+    pub fn index_of(src: Source) -> Self {
+        let str_repr: String = "IndexOf".to_string();
+        Self::IndexOf { str_repr, src }
+    }
+
+    pub fn min(src: Source) -> Self {
+        let str_repr: String = "Min".to_string();
+        Self::Min { str_repr, src }
+    }
+
+    pub fn max(src: Source) -> Self {
+        let str_repr: String = "Max".to_string();
+        Self::Max { str_repr, src }
+    }
+
+    pub fn sum(src: Source) -> Self {
+        let str_repr: String = "Sum".to_string();
+        Self::Sum { str_repr, src }
+    }
+
+    pub fn join(src: Source) -> Self {
+        let str_repr: String = "Join".to_string();
+        Self::Join { str_repr, src }
+    }
+
+    pub fn iter_new(src: Source) -> Self {
+        let str_repr: String = "IterNew".to_string();
+        Self::IterNew { str_repr, src }
+    }
+
+    pub fn iter_next(br_index: usize, src: Source) -> Self {
+        let str_repr: String = format!("IterNext {}", br_index);
+        Self::IterNext {
+            br_index,
+            str_repr,
+            src,
+        }
+    }
+
+    pub fn range(src: Source) -> Self {
+        let str_repr: String = "Range".to_string();
+        Self::Range { str_repr, src }
+    }
+
+    pub fn range_step(src: Source) -> Self {
+        let str_repr: String = "RangeStep".to_string();
+        Self::RangeStep { str_repr, src }
+    }
+
+    pub fn from_parts(name: &str, operand: Operand, src: Source) -> Result<Self, String> {
+        match (name.to_ascii_lowercase().as_str(), operand) {
+            ("nop", Operand::None) => Ok(Self::nop_instruction(src)),
+            ("load", Operand::Value(value)) => Ok(Self::load_instruction(value, src)),
+            ("add", Operand::None) => Ok(Self::add(src)),
+            ("sub", Operand::None) => Ok(Self::sub(src)),
+            ("mul", Operand::None) => Ok(Self::mul(src)),
+            ("div", Operand::None) => Ok(Self::div(src)),
+            ("br", Operand::Index(index)) => Ok(Self::br(index, src)),
+            ("beq", Operand::Index(index)) => Ok(Self::beq(index, src)),
+            ("bnq", Operand::Index(index)) => Ok(Self::bnq(index, src)),
+            ("bgt", Operand::Index(index)) => Ok(Self::bgt(index, src)),
+            ("bge", Operand::Index(index)) => Ok(Self::bge(index, src)),
+            ("blt", Operand::Index(index)) => Ok(Self::blt(index, src)),
+            ("ble", Operand::Index(index)) => Ok(Self::ble(index, src)),
+            ("call", Operand::Index(index)) => Ok(Self::call(index, src)),
+            ("ret", Operand::None) => Ok(Self::ret(src)),
+            ("print", Operand::None) => Ok(Self::print(src)),
+            ("gload", Operand::Name(name)) => Ok(Self::gload(&name, src)),
+            ("gstore", Operand::Name(name)) => Ok(Self::gstore(&name, src)),
+            ("throw", Operand::None) => Ok(Self::throw(src)),
+            ("trystart", Operand::Index(index)) => Ok(Self::try_start(index, src)),
+            ("tryend", Operand::None) => Ok(Self::try_end(src)),
+            ("rem", Operand::None) => Ok(Self::rem(src)),
+            ("mod", Operand::None) => Ok(Self::modulo(src)),
+            ("neg", Operand::None) => Ok(Self::neg(src)),
+            ("pow", Operand::None) => Ok(Self::pow(src)),
+            ("abs", Operand::None) => Ok(Self::abs(src)),
+            ("floordiv", Operand::None) => Ok(Self::floor_div(src)),
+            ("truediv", Operand::None) => Ok(Self::true_div(src)),
+            ("and", Operand::None) => Ok(Self::bit_and(src)),
+            ("or", Operand::None) => Ok(Self::bit_or(src)),
+            ("xor", Operand::None) => Ok(Self::bit_xor(src)),
+            ("not", Operand::None) => Ok(Self::bit_not(src)),
+            ("shl", Operand::None) => Ok(Self::shl(src)),
+            ("sar", Operand::None) => Ok(Self::sar(src)),
+            ("shr", Operand::None) => Ok(Self::shr(src)),
+            ("popcount", Operand::None) => Ok(Self::popcount(src)),
+            ("native", Operand::Name(name)) => Ok(Self::native(&name, src)),
+            ("toint", Operand::None) => Ok(Self::to_int(src)),
+            ("todouble", Operand::None) => Ok(Self::to_double(src)),
+            ("tostr", Operand::None) => Ok(Self::to_str(src)),
+            ("tobool", Operand::None) => Ok(Self::to_bool(src)),
+            ("parsenum", Operand::None) => Ok(Self::parse_num(src)),
+            ("typeof", Operand::None) => Ok(Self::type_of(src)),
+            ("isint", Operand::None) => Ok(Self::is_int(src)),
+            ("isdouble", Operand::None) => Ok(Self::is_double(src)),
+            ("isstr", Operand::None) => Ok(Self::is_str(src)),
+            ("isbool", Operand::None) => Ok(Self::is_bool(src)),
+            ("isnull", Operand::None) => Ok(Self::is_null(src)),
+            ("iserror", Operand::None) => Ok(Self::is_error(src)),
+            ("asserttype", Operand::Name(kind)) => match VobjKind::from_name(&kind) {
+                Some(kind) => Ok(Self::assert_type(kind, src)),
+                None => Err(format!("unknown kind `{}`", kind)),
+            },
+            ("bnull", Operand::Index(index)) => Ok(Self::bnull(index, src)),
+            ("bnnull", Operand::Index(index)) => Ok(Self::bnnull(index, src)),
+            ("coalesce", Operand::None) => Ok(Self::coalesce(src)),
+            ("cmp", Operand::None) => Ok(Self::cmp(src)),
+            ("makelist", Operand::None) => Ok(Self::make_list(src)),
+            ("len", Operand::None) => Ok(Self::len(src)),
+            ("get", Operand::None) => Ok(Self::get(src)),
+            ("sort", Operand::None) => Ok(Self::sort(src)),
+            ("sortby", Operand::Index(index)) => Ok(Self::sort_by(index, src)),
+            ("reverse", Operand::None) => Ok(Self::reverse(src)),
+            ("contains", Operand::None) => Ok(Self::contains(src)),
+            ("indexof", Operand::None) => Ok(Self::index_of(src)),
+            ("min", Operand::None) => Ok(Self::min(src)),
+            ("max", Operand::None) => Ok(Self::max(src)),
+            ("sum", Operand::None) => Ok(Self::sum(src)),
+            ("join", Operand::None) => Ok(Self::join(src)),
+            ("iternew", Operand::None) => Ok(Self::iter_new(src)),
+            ("iternext", Operand::Index(index)) => Ok(Self::iter_next(index, src)),
+            ("range", Operand::None) => Ok(Self::range(src)),
+            ("rangestep", Operand::None) => Ok(Self::range_step(src)),
+            _ if Self::operand_kind(name).is_some() => {
+                Err(format!("invalid operand for `{}`", name))
+            }
+            _ => Err(format!("unknown instruction `{}`", name)),
+        }
+    }
+
+    pub fn operand_kind(name: &str) -> Option<OperandKind> {
+        match name.to_ascii_lowercase().as_str() {
+            "nop" | "add" | "sub" | "mul" | "div" | "ret" | "print" | "throw" | "tryend" | "rem"
+            | "mod" | "neg" | "pow" | "abs" | "floordiv" | "truediv" | "and" | "or" | "xor"
+            | "not" | "shl" | "sar" | "shr" | "popcount" | "toint" | "todouble" | "tostr"
+            | "tobool" | "parsenum" | "typeof" | "isint" | "isdouble" | "isstr" | "isbool"
+            | "isnull" | "iserror" | "coalesce" | "cmp" | "makelist" | "len" | "get" | "sort"
+            | "reverse" | "contains" | "indexof" | "min" | "max" | "sum" | "join" | "iternew"
+            | "range" | "rangestep" => {
+                Some(OperandKind::None)
+            }
+            "load" => Some(OperandKind::Value),
+            "br" | "beq" | "bnq" | "bgt" | "bge" | "blt" | "ble" | "call" | "trystart" | "bnull"
+            | "bnnull" | "sortby" | "iternext" => Some(OperandKind::Index),
+            "gload" | "gstore" | "native" | "asserttype" => Some(OperandKind::Name),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Nop { .. } => "Nop",
+            Self::Load { .. } => "Load",
+            Self::Add { .. } => "Add",
+            Self::Sub { .. } => "Sub",
+            Self::Mul { .. } => "Mul",
+            Self::Div { .. } => "Div",
+            Self::Br { .. } => "Br",
+            Self::Beq { .. } => "Beq",
+            Self::Bnq { .. } => "Bnq",
+            Self::Bg { .. } => "Bgt",
+            Self::Bge { .. } => "Bge",
+            Self::Blt { .. } => "Blt",
+            Self::Ble { .. } => "Ble",
+            Self::Bgt { .. } => "Bgt",
+            Self::Call { .. } => "Call",
+            Self::Ret { .. } => "Ret",
+            Self::Print { .. } => "Print",
+            Self::GLoad { .. } => "GLoad",
+            Self::GStore { .. } => "GStore",
+            Self::Throw { .. } => "Throw",
+            Self::TryStart { .. } => "TryStart",
+            Self::TryEnd { .. } => "TryEnd",
+            Self::Rem { .. } => "Rem",
+            Self::Mod { .. } => "Mod",
+            Self::Neg { .. } => "Neg",
+            Self::Pow { .. } => "Pow",
+            Self::Abs { .. } => "Abs",
+            Self::FloorDiv { .. } => "FloorDiv",
+            Self::TrueDiv { .. } => "TrueDiv",
+            Self::And { .. } => "And",
+            Self::Or { .. } => "Or",
+            Self::Xor { .. } => "Xor",
+            Self::Not { .. } => "Not",
+            Self::Shl { .. } => "Shl",
+            Self::Sar { .. } => "Sar",
+            Self::Shr { .. } => "Shr",
+            Self::Popcount { .. } => "Popcount",
+            Self::Native { .. } => "Native",
+            Self::ToInt { .. } => "ToInt",
+            Self::ToDouble { .. } => "ToDouble",
+            Self::ToStr { .. } => "ToStr",
+            Self::ToBool { .. } => "ToBool",
+            Self::ParseNum { .. } => "ParseNum",
+            Self::TypeOf { .. } => "TypeOf",
+            Self::IsInt { .. } => "IsInt",
+            Self::IsDouble { .. } => "IsDouble",
+            Self::IsStr { .. } => "IsStr",
+            Self::IsBool { .. } => "IsBool",
+            Self::IsNull { .. } => "IsNull",
+            Self::IsError { .. } => "IsError",
+            Self::AssertType { .. } => "AssertType",
+            Self::Bnull { .. } => "Bnull",
+            Self::Bnnull { .. } => "Bnnull",
+            Self::Coalesce { .. } => "Coalesce",
+            Self::Cmp { .. } => "Cmp",
+            Self::MakeList { .. } => "MakeList",
+            Self::Len { .. } => "Len",
+            Self::Get { .. } => "Get",
+            Self::Sort { .. } => "Sort",
+            Self::SortBy { .. } => "SortBy",
+            Self::Reverse { .. } => "Reverse",
+            Self::Contains { .. } => "Contains",
+            Self::IndexOf { .. } => "IndexOf",
+            Self::Min { .. } => "Min",
+            Self::Max { .. } => "Max",
+            Self::Sum { .. } => "Sum",
+            Self::Join { .. } => "Join",
+            Self::IterNew { .. } => "IterNew",
+            Self::IterNext { .. } => "IterNext",
+            Self::Range { .. } => "Range",
+            Self::RangeStep { .. } => "RangeStep",
+        }
+    }
+
+    pub fn operand(&self) -> Operand {
+        match self {
+            Self::Load { value, .. } => Operand::Value(value.clone()),
+            Self::Br { br_index, .. }
+            | Self::Beq { br_index, .. }
+            | Self::Bnq { br_index, .. }
+            | Self::Bg { br_index, .. }
+            | Self::Bge { br_index, .. }
+            | Self::Blt { br_index, .. }
+            | Self::Ble { br_index, .. }
+            | Self::Bgt { br_index, .. }
+            | Self::Call { br_index, .. }
+            | Self::TryStart { br_index, .. }
+            | Self::Bnull { br_index, .. }
+            | Self::Bnnull { br_index, .. }
+            | Self::SortBy { br_index, .. }
+            | Self::IterNext { br_index, .. } => Operand::Index(*br_index),
+            Self::GLoad { name, .. }
+            | Self::GStore { name, .. }
+            | Self::Native { name, .. } => Operand::Name(name.clone()),
+            Self::AssertType { kind, .. } => Operand::Name(kind.name().to_string()),
+            _ => Operand::None,
+        }
+    }
+
+    pub fn str_repr(&self) -> &str {
+        match self {
+            Self::Nop { str_repr, .. }
+            | Self::Load { str_repr, .. }
+            | Self::Add { str_repr, .. }
+            | Self::Sub { str_repr, .. }
+            | Self::Mul { str_repr, .. }
+            | Self::Div { str_repr, .. }
+            | Self::Br { str_repr, .. }
+            | Self::Beq { str_repr, .. }
+            | Self::Bnq { str_repr, .. }
+            | Self::Bg { str_repr, .. }
+            | Self::Bge { str_repr, .. }
+            | Self::Blt { str_repr, .. }
+            | Self::Ble { str_repr, .. }
+            | Self::Bgt { str_repr, .. }
+            | Self::Call { str_repr, .. }
+            | Self::Ret { str_repr, .. }
+            | Self::Print { str_repr, .. }
+            | Self::GLoad { str_repr, .. }
+            | Self::GStore { str_repr, .. }
+            | Self::Throw { str_repr, .. }
+            | Self::TryStart { str_repr, .. }
+            | Self::TryEnd { str_repr, .. }
+            | Self::Rem { str_repr, .. }
+            | Self::Mod { str_repr, .. }
+            | Self::Neg { str_repr, .. }
+            | Self::Pow { str_repr, .. }
+            | Self::Abs { str_repr, .. }
+            | Self::FloorDiv { str_repr, .. }
+            | Self::TrueDiv { str_repr, .. }
+            | Self::And { str_repr, .. }
+            | Self::Or { str_repr, .. }
+            | Self::Xor { str_repr, .. }
+            | Self::Not { str_repr, .. }
+            | Self::Shl { str_repr, .. }
+            | Self::Sar { str_repr, .. }
+            | Self::Shr { str_repr, .. }
+            | Self::Popcount { str_repr, .. }
+            | Self::Native { str_repr, .. }
+            | Self::ToInt { str_repr, .. }
+            | Self::ToDouble { str_repr, .. }
+            | Self::ToStr { str_repr, .. }
+            | Self::ToBool { str_repr, .. }
+            | Self::ParseNum { str_repr, .. }
+            | Self::TypeOf { str_repr, .. }
+            | Self::IsInt { str_repr, .. }
+            | Self::IsDouble { str_repr, .. }
+            | Self::IsStr { str_repr, .. }
+            | Self::IsBool { str_repr, .. }
+            | Self::IsNull { str_repr, .. }
+            | Self::IsError { str_repr, .. }
+            | Self::AssertType { str_repr, .. }
+            | Self::Bnull { str_repr, .. }
+            | Self::Bnnull { str_repr, .. }
+            | Self::Coalesce { str_repr, .. }
+            | Self::Cmp { str_repr, .. }
+            | Self::MakeList { str_repr, .. }
+            | Self::Len { str_repr, .. }
+            | Self::Get { str_repr, .. }
+            | Self::Sort { str_repr, .. }
+            | Self::SortBy { str_repr, .. }
+            | Self::Reverse { str_repr, .. }
+            | Self::Contains { str_repr, .. }
+            | Self::IndexOf { str_repr, .. }
+            | Self::Min { str_repr, .. }
+            | Self::Max { str_repr, .. }
+            | Self::Sum { str_repr, .. }
+            | Self::Join { str_repr, .. }
+            | Self::IterNew { str_repr, .. }
+            | Self::IterNext { str_repr, .. }
+            | Self::Range { str_repr, .. }
+            | Self::RangeStep { str_repr, .. } => str_repr,
+        }
+    }
+
+    pub fn src(&self) -> &Source {
+        match self {
+            Self::Nop { src, .. }
+            | Self::Load { src, .. }
+            | Self::Add { src, .. }
+            | Self::Sub { src, .. }
+            | Self::Mul { src, .. }
+            | Self::Div { src, .. }
+            | Self::Br { src, .. }
+            | Self::Beq { src, .. }
+            | Self::Bnq { src, .. }
+            | Self::Bg { src, .. }
+            | Self::Bge { src, .. }
+            | Self::Blt { src, .. }
+            | Self::Ble { src, .. }
+            | Self::Bgt { src, .. }
+            | Self::Call { src, .. }
+            | Self::Ret { src, .. }
+            | Self::Print { src, .. }
+            | Self::GLoad { src, .. }
+            | Self::GStore { src, .. }
+            | Self::Throw { src, .. }
+            | Self::TryStart { src, .. }
+            | Self::TryEnd { src, .. }
+            | Self::Rem { src, .. }
+            | Self::Mod { src, .. }
+            | Self::Neg { src, .. }
+            | Self::Pow { src, .. }
+            | Self::Abs { src, .. }
+            | Self::FloorDiv { src, .. }
+            | Self::TrueDiv { src, .. }
+            | Self::And { src, .. }
+            | Self::Or { src, .. }
+            | Self::Xor { src, .. }
+            | Self::Not { src, .. }
+            | Self::Shl { src, .. }
+            | Self::Sar { src, .. }
+            | Self::Shr { src, .. }
+            | Self::Popcount { src, .. }
+            | Self::Native { src, .. }
+            | Self::ToInt { src, .. }
+            | Self::ToDouble { src, .. }
+            | Self::ToStr { src, .. }
+            | Self::ToBool { src, .. }
+            | Self::ParseNum { src, .. }
+            | Self::TypeOf { src, .. }
+            | Self::IsInt { src, .. }
+            | Self::IsDouble { src, .. }
+            | Self::IsStr { src, .. }
+            | Self::IsBool { src, .. }
+            | Self::IsNull { src, .. }
+            | Self::IsError { src, .. }
+            | Self::AssertType { src, .. }
+            | Self::Bnull { src, .. }
+            | Self::Bnnull { src, .. }
+            | Self::Coalesce { src, .. }
+            | Self::Cmp { src, .. }
+            | Self::MakeList { src, .. }
+            | Self::Len { src, .. }
+            | Self::Get { src, .. }
+            | Self::Sort { src, .. }
+            | Self::SortBy { src, .. }
+            | Self::Reverse { src, .. }
+            | Self::Contains { src, .. }
+            | Self::IndexOf { src, .. }
+            | Self::Min { src, .. }
+            | Self::Max { src, .. }
+            | Self::Sum { src, .. }
+            | Self::Join { src, .. }
+            | Self::IterNew { src, .. }
+            | Self::IterNext { src, .. }
+            | Self::Range { src, .. }
+            | Self::RangeStep { src, .. } => src,
+        }
+    }
 }
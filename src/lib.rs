@@ -2,3 +2,10 @@ pub mod vobj;
 pub mod instruction;
 pub mod err;
 pub mod vm;
+pub mod program;
+pub mod asm;
+pub mod bytecode;
+pub mod trace;
+pub mod profile;
+pub mod diagnostic;
+pub mod native;
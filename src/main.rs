@@ -0,0 +1,263 @@
+use std::env;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::process::ExitCode;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use vm::asm;
+use vm::bytecode;
+use vm::diagnostic::Diagnostic;
+use vm::program::Program;
+use vm::trace::Tracer;
+use vm::vm::{RunOutcome, VmBuilder, VmConfig};
+
+const USAGE: &str = "usage:
+  vm run [--trace] [--profile] [--folded <out>] [--limit <n>] [--timeout <ms>]
+         [--dump-stack] [--null-propagation] <file>
+  vm build <file.zt> -o <out>
+  vm disasm <file>
+
+<file> may be `.zt` assembly or compiled bytecode; `-` reads standard input.";
+
+struct RunOptions {
+    trace: bool,
+    profile: bool,
+    folded: Option<String>,
+    limit: Option<u64>,
+    timeout: Option<Duration>,
+    dump_stack: bool,
+    null_propagation: bool,
+}
+
+// A plain message, or diagnostics that already carry their own error kind.
+enum Failure {
+    Message(String),
+    Rendered(String),
+}
+
+impl From<String> for Failure {
+    fn from(message: String) -> Self {
+        Failure::Message(message)
+    }
+}
+
+impl From<&str> for Failure {
+    fn from(message: &str) -> Self {
+        Failure::Message(message.to_string())
+    }
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    match dispatch(&args) {
+        Ok(code) => code,
+        Err(Failure::Message(message)) => {
+            eprintln!("error: {}", message);
+            ExitCode::from(2)
+        }
+        Err(Failure::Rendered(rendered)) => {
+            eprint!("{}", rendered);
+            ExitCode::from(2)
+        }
+    }
+}
+
+fn dispatch(args: &[String]) -> Result<ExitCode, Failure> {
+    let (command, rest) = args.split_first().ok_or_else(|| USAGE.to_string())?;
+    match command.as_str() {
+        "run" => {
+            let mut options = RunOptions {
+                trace: false,
+                profile: false,
+                folded: None,
+                limit: None,
+                timeout: None,
+                dump_stack: false,
+                null_propagation: false,
+            };
+            let mut path = None;
+            let mut rest = rest.iter();
+            while let Some(arg) = rest.next() {
+                match arg.as_str() {
+                    "--trace" => options.trace = true,
+                    "--profile" => options.profile = true,
+                    "--folded" => {
+                        let value = rest.next().ok_or("--folded requires a value")?;
+                        options.folded = Some(value.clone());
+                    }
+                    "--dump-stack" => options.dump_stack = true,
+                    "--null-propagation" => options.null_propagation = true,
+                    "--limit" => {
+                        let value = rest.next().ok_or("--limit requires a value")?;
+                        let limit = value
+                            .parse()
+                            .map_err(|_| format!("invalid instruction limit `{}`", value))?;
+                        options.limit = Some(limit);
+                    }
+                    "--timeout" => {
+                        let value = rest.next().ok_or("--timeout requires a value")?;
+                        let millis = value
+                            .parse()
+                            .map_err(|_| format!("invalid timeout `{}`", value))?;
+                        options.timeout = Some(Duration::from_millis(millis));
+                    }
+                    _ => path = Some(positional(arg, path)?),
+                }
+            }
+            let path = path.ok_or_else(|| USAGE.to_string())?;
+            let bytes = read_input(&path)?;
+            let text = source_text(&bytes);
+            let program = verify(load(&path, &bytes)?, text)?;
+            Ok(run(program, text, &options))
+        }
+        "build" => {
+            let mut output = None;
+            let mut path = None;
+            let mut rest = rest.iter();
+            while let Some(arg) = rest.next() {
+                match arg.as_str() {
+                    "-o" => output = Some(rest.next().ok_or("-o requires a value")?.clone()),
+                    _ => path = Some(positional(arg, path)?),
+                }
+            }
+            let path = path.ok_or_else(|| USAGE.to_string())?;
+            let output = output.ok_or("missing output file (-o)")?;
+            let bytes = read_input(&path)?;
+            let program = verify(load(&path, &bytes)?, source_text(&bytes))?;
+            let encoded = bytecode::encode(program.code());
+            let written = if output == "-" {
+                io::stdout().write_all(&encoded)
+            } else {
+                fs::write(&output, encoded)
+            };
+            written.map_err(|error| format!("{}: {}", output, error))?;
+            Ok(ExitCode::SUCCESS)
+        }
+        "disasm" => {
+            let path = match rest {
+                [path] => path,
+                _ => return Err(USAGE.into()),
+            };
+            let bytes = read_input(path)?;
+            let program = load(path, &bytes)?;
+            print!("{}", asm::disassemble(program.code()));
+            Ok(ExitCode::SUCCESS)
+        }
+        "help" | "--help" | "-h" => {
+            println!("{}", USAGE);
+            Ok(ExitCode::SUCCESS)
+        }
+        _ => Err(format!("unknown command `{}`\n{}", command, USAGE).into()),
+    }
+}
+
+fn positional(arg: &str, previous: Option<String>) -> Result<String, String> {
+    if arg.starts_with("--") {
+        return Err(format!("unknown option `{}`", arg));
+    }
+    match previous {
+        Some(_) => Err(format!("unexpected argument `{}`", arg)),
+        None => Ok(arg.to_string()),
+    }
+}
+
+fn read_input(path: &str) -> Result<Vec<u8>, String> {
+    if path == "-" {
+        let mut bytes = Vec::new();
+        io::stdin()
+            .read_to_end(&mut bytes)
+            .map_err(|error| format!("<stdin>: {}", error))?;
+        Ok(bytes)
+    } else {
+        fs::read(path).map_err(|error| format!("{}: {}", path, error))
+    }
+}
+
+fn load(path: &str, bytes: &[u8]) -> Result<Program, Failure> {
+    if bytecode::is_bytecode(bytes) {
+        let code = bytecode::decode(bytes).map_err(|error| format!("{}: {}", path, error))?;
+        return Ok(Program::new(code));
+    }
+    let text = std::str::from_utf8(bytes).map_err(|_| format!("{}: not valid utf-8", path))?;
+    asm::assemble_program(text, path)
+        .map_err(|error| Failure::Rendered(Diagnostic::from(&error).render(text)))
+}
+
+// Bytecode carries no text to quote, so its diagnostics only name locations.
+fn source_text(bytes: &[u8]) -> &str {
+    if bytecode::is_bytecode(bytes) {
+        return "";
+    }
+    std::str::from_utf8(bytes).unwrap_or("")
+}
+
+fn verify(program: Program, text: &str) -> Result<Program, Failure> {
+    program.verify().map_err(|diagnostics| {
+        let rendered: Vec<String> = diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.render(text))
+            .collect();
+        Failure::Rendered(rendered.join("\n"))
+    })?;
+    Ok(program)
+}
+
+fn run(program: Program, text: &str, options: &RunOptions) -> ExitCode {
+    let mut builder = VmBuilder::from_program(Arc::new(program)).config(VmConfig {
+        null_propagation: options.null_propagation,
+        ..VmConfig::default()
+    });
+    if let Some(limit) = options.limit {
+        builder = builder.fuel(limit);
+    }
+    if let Some(timeout) = options.timeout {
+        builder = builder.deadline(Instant::now() + timeout);
+    }
+    if options.trace {
+        builder = builder.observer(Box::new(Tracer::stderr()));
+    }
+    let mut vm = match builder.build() {
+        Ok(vm) => vm,
+        Err(error) => {
+            eprintln!("error: {}", error);
+            return ExitCode::from(2);
+        }
+    };
+    let profiler = (options.profile || options.folded.is_some()).then(|| vm.enable_profiling());
+    let result = match vm.run_budgeted() {
+        Ok(RunOutcome::Finished) => Ok(()),
+        Ok(RunOutcome::OutOfFuel) => Err(format!(
+            "error: instruction limit of {} reached\n",
+            vm.instructions_executed()
+        )),
+        Ok(RunOutcome::DeadlineExceeded) => Err(format!(
+            "error: timed out after {} instructions\n",
+            vm.instructions_executed()
+        )),
+        Err(error) => Err(Diagnostic::from(&*error).render(text)),
+    };
+
+    if let Some(profiler) = profiler {
+        if options.profile {
+            eprint!("{}", profiler.report());
+        }
+        if let Some(path) = &options.folded {
+            if let Err(error) = fs::write(path, profiler.folded()) {
+                eprintln!("error: {}: {}", path, error);
+            }
+        }
+    }
+    if options.dump_stack {
+        for value in vm.dump_mem().iter() {
+            println!("{}", asm::format_value(value));
+        }
+    }
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(message) => {
+            eprint!("{}", message);
+            ExitCode::FAILURE
+        }
+    }
+}
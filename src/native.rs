@@ -0,0 +1,203 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, OnceLock};
+
+use super::err::{Position, VMError};
+use super::vobj::{Vobj, VobjKind};
+
+pub type NativeFn = dyn Fn(&[Vobj]) -> Result<Vobj, VMError> + Send + Sync;
+
+type Math<T> = (&'static str, fn(f64) -> T);
+
+/// A host function callable with `native <name>`. Its arguments are popped
+/// from the stack, the first one pushed being the first argument.
+#[derive(Clone)]
+pub struct Native {
+    arity: usize,
+    function: Arc<NativeFn>,
+}
+
+impl Native {
+    pub fn arity(&self) -> usize {
+        self.arity
+    }
+
+    pub fn call(&self, args: &[Vobj]) -> Result<Vobj, VMError> {
+        (self.function)(args)
+    }
+}
+
+/// The natives a vm can call, by name.
+#[derive(Clone, Default)]
+pub struct Natives {
+    functions: HashMap<String, Native>,
+}
+
+impl Natives {
+    /// A registry with no functions at all.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The registry every vm starts with: the math library.
+    pub fn standard() -> Arc<Natives> {
+        static STANDARD: OnceLock<Arc<Natives>> = OnceLock::new();
+        Arc::clone(STANDARD.get_or_init(|| {
+            let mut natives = Natives::new();
+            natives.register_math();
+            Arc::new(natives)
+        }))
+    }
+
+    /// Adds `function`, replacing any native already registered as `name`.
+    pub fn register(
+        &mut self,
+        name: &str,
+        arity: usize,
+        function: impl Fn(&[Vobj]) -> Result<Vobj, VMError> + Send + Sync + 'static,
+    ) {
+        self.functions.insert(
+            name.to_string(),
+            Native {
+                arity,
+                function: Arc::new(function),
+            },
+        );
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Native> {
+        self.functions.get(name)
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.functions.keys().map(String::as_str)
+    }
+
+    /// sqrt, sin, cos, tan, atan2, exp, ln, log10, floor, ceil, round, trunc,
+    /// min, max, clamp, is_nan, is_infinite and is_finite.
+    pub fn register_math(&mut self) {
+        let unary: [Math<f64>; 7] = [
+            ("sqrt", f64::sqrt),
+            ("sin", f64::sin),
+            ("cos", f64::cos),
+            ("tan", f64::tan),
+            ("exp", f64::exp),
+            ("ln", f64::ln),
+            ("log10", f64::log10),
+        ];
+        for (name, function) in unary {
+            self.register(name, 1, move |args| {
+                Ok(Vobj::Double(function(number(name, args, 0)?)))
+            });
+        }
+
+        // an Int is already whole, so rounding leaves it an Int
+        let rounding: [Math<f64>; 4] = [
+            ("floor", f64::floor),
+            ("ceil", f64::ceil),
+            ("round", f64::round),
+            ("trunc", f64::trunc),
+        ];
+        for (name, function) in rounding {
+            self.register(name, 1, move |args| match args[0] {
+                Vobj::Int(value) => Ok(Vobj::Int(value)),
+                _ => Ok(Vobj::Double(function(number(name, args, 0)?))),
+            });
+        }
+
+        let predicates: [Math<bool>; 3] = [
+            ("is_nan", f64::is_nan),
+            ("is_infinite", f64::is_infinite),
+            ("is_finite", f64::is_finite),
+        ];
+        for (name, function) in predicates {
+            self.register(name, 1, move |args| {
+                Ok(Vobj::Bool(function(number(name, args, 0)?)))
+            });
+        }
+
+        self.register("atan2", 2, |args| {
+            let y = number("atan2", args, 0)?;
+            let x = number("atan2", args, 1)?;
+            Ok(Vobj::Double(y.atan2(x)))
+        });
+        self.register("min", 2, |args| {
+            numbers("min", args)?;
+            let picked = if args[0].compare(&args[1]).is_le() {
+                &args[0]
+            } else {
+                &args[1]
+            };
+            Ok(promote(args, picked))
+        });
+        self.register("max", 2, |args| {
+            numbers("max", args)?;
+            let picked = if args[0].compare(&args[1]).is_ge() {
+                &args[0]
+            } else {
+                &args[1]
+            };
+            Ok(promote(args, picked))
+        });
+        self.register("clamp", 3, |args| {
+            numbers("clamp", args)?;
+            let (value, low, high) = (&args[0], &args[1], &args[2]);
+            if low.compare(high).is_gt() {
+                return Err(VMError::IncorrectArgumentErr(
+                    "clamp: lower bound is greater than upper bound".to_string(),
+                ));
+            }
+            let clamped = if value.compare(low).is_lt() {
+                low
+            } else if value.compare(high).is_gt() {
+                high
+            } else {
+                value
+            };
+            Ok(promote(args, clamped))
+        });
+    }
+}
+
+impl fmt::Debug for Natives {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut names: Vec<&str> = self.names().collect();
+        names.sort();
+        f.debug_struct("Natives")
+            .field("functions", &names)
+            .finish()
+    }
+}
+
+// The numeric value of argument `index`, with Int promoted like `Vobj::add`
+// does.
+fn number(operation: &'static str, args: &[Vobj], index: usize) -> Result<f64, VMError> {
+    match args[index] {
+        Vobj::Int(value) => Ok(value as f64),
+        Vobj::Double(value) => Ok(value),
+        ref actual => Err(VMError::TypeErr {
+            operation,
+            position: Position::Argument(index + 1),
+            expected: vec![VobjKind::Int, VobjKind::Double],
+            actual: actual.kind(),
+        }),
+    }
+}
+
+// Checks that every argument is a number, without converting any of them.
+fn numbers(operation: &'static str, args: &[Vobj]) -> Result<(), VMError> {
+    for index in 0..args.len() {
+        number(operation, args, index)?;
+    }
+    Ok(())
+}
+
+// The picked argument, staying an Int only when all the arguments are. Only
+// the result is converted, so choosing between large ints stays exact.
+fn promote(args: &[Vobj], picked: &Vobj) -> Vobj {
+    let all_int = args.iter().all(|arg| matches!(arg, Vobj::Int(_)));
+    match picked {
+        Vobj::Int(value) if !all_int => Vobj::Double(*value as f64),
+        picked => picked.clone(),
+    }
+}
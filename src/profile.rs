@@ -0,0 +1,220 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use super::asm;
+use super::err::Source;
+use super::instruction::Instruction;
+use super::trace::Observer;
+use super::vobj::Vobj;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stat {
+    pub label: String,
+    pub count: u64,
+    pub time: Duration,
+}
+
+impl Stat {
+    fn new(label: String) -> Self {
+        Self {
+            label,
+            count: 0,
+            time: Duration::ZERO,
+        }
+    }
+
+    fn record(&mut self, elapsed: Duration) {
+        self.count += 1;
+        self.time += elapsed;
+    }
+}
+
+/// Profile of a run, every table sorted by accumulated time, most expensive first.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProfileReport {
+    pub instructions: Vec<Stat>,
+    pub opcodes: Vec<Stat>,
+    pub lines: Vec<Stat>,
+}
+
+impl fmt::Display for ProfileReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let tables = [
+            ("instruction", &self.instructions),
+            ("opcode", &self.opcodes),
+            ("source line", &self.lines),
+        ];
+        for (index, (title, stats)) in tables.iter().enumerate() {
+            if index > 0 {
+                writeln!(f)?;
+            }
+            writeln!(f, "{:<32} {:>10} {:>12}", title, "count", "time (ns)")?;
+            for stat in stats.iter() {
+                writeln!(
+                    f,
+                    "{:<32} {:>10} {:>12}",
+                    stat.label,
+                    stat.count,
+                    stat.time.as_nanos()
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default)]
+struct ProfileData {
+    instructions: HashMap<usize, Stat>,
+    opcodes: HashMap<&'static str, Stat>,
+    lines: HashMap<String, Stat>,
+    folded: HashMap<String, Duration>,
+    frames: Vec<String>,
+    // one entry per instruction still executing
+    started: Vec<(Instant, String)>,
+}
+
+impl ProfileData {
+    fn finish(&mut self, pc: usize, instruction: &Instruction, src: &Source) {
+        let (started, stack) = match self.started.pop() {
+            Some(started) => started,
+            None => return,
+        };
+        let elapsed = started.elapsed();
+        self.instructions
+            .entry(pc)
+            .or_insert_with(|| {
+                Stat::new(format!(
+                    "{:>4}  {}",
+                    pc,
+                    asm::format_instruction(instruction)
+                ))
+            })
+            .record(elapsed);
+        self.opcodes
+            .entry(instruction.name())
+            .or_insert_with(|| Stat::new(instruction.name().to_string()))
+            .record(elapsed);
+        let line = format!("{}:{}", src.source_file(), src.line_number());
+        self.lines
+            .entry(line.clone())
+            .or_insert_with(|| Stat::new(line))
+            .record(elapsed);
+        *self
+            .folded
+            .entry(format!("{};{}", stack, instruction.name()))
+            .or_default() += elapsed;
+    }
+}
+
+/// Observer counting executions and time per instruction index, opcode and
+/// source line. Clones share the same data, so keep one to read the report.
+#[derive(Debug, Clone, Default)]
+pub struct Profiler {
+    data: Arc<Mutex<ProfileData>>,
+}
+
+impl Profiler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn report(&self) -> ProfileReport {
+        let data = self.data.lock().expect("profile poisoned");
+        ProfileReport {
+            instructions: sorted(data.instructions.values()),
+            opcodes: sorted(data.opcodes.values()),
+            lines: sorted(data.lines.values()),
+        }
+    }
+
+    /// Folded stacks (`main;fn@5;Add 1200`) weighted in nanoseconds, the input
+    /// format of flamegraph tools.
+    pub fn folded(&self) -> String {
+        let data = self.data.lock().expect("profile poisoned");
+        let mut lines: Vec<String> = data
+            .folded
+            .iter()
+            .map(|(stack, time)| format!("{} {}", stack, time.as_nanos()))
+            .collect();
+        lines.sort();
+        let mut folded = lines.join("\n");
+        if !folded.is_empty() {
+            folded.push('\n');
+        }
+        folded
+    }
+}
+
+fn sorted<'a>(stats: impl Iterator<Item = &'a Stat>) -> Vec<Stat> {
+    let mut stats: Vec<Stat> = stats.cloned().collect();
+    stats.sort_by(|a, b| {
+        b.time
+            .cmp(&a.time)
+            .then(b.count.cmp(&a.count))
+            .then(a.label.cmp(&b.label))
+    });
+    stats
+}
+
+impl Observer for Profiler {
+    fn before_instruction(
+        &mut self,
+        _pc: usize,
+        _instruction: &Instruction,
+        _src: &Source,
+        _stack: &[Vobj],
+    ) {
+        let mut data = self.data.lock().expect("profile poisoned");
+        let mut stack = String::from("main");
+        for frame in data.frames.iter() {
+            stack.push(';');
+            stack.push_str(frame);
+        }
+        data.started.push((Instant::now(), stack));
+    }
+
+    fn after_instruction(
+        &mut self,
+        pc: usize,
+        instruction: &Instruction,
+        src: &Source,
+        _stack: &[Vobj],
+    ) {
+        self.data
+            .lock()
+            .expect("profile poisoned")
+            .finish(pc, instruction, src);
+    }
+
+    fn on_error(
+        &mut self,
+        pc: usize,
+        instruction: &Instruction,
+        src: &Source,
+        _stack: &[Vobj],
+        _error: &str,
+    ) {
+        self.data
+            .lock()
+            .expect("profile poisoned")
+            .finish(pc, instruction, src);
+    }
+
+    fn on_call(&mut self, _pc: usize, target: usize, _src: &Source, _stack: &[Vobj]) {
+        let mut data = self.data.lock().expect("profile poisoned");
+        data.frames.push(format!("fn@{}", target));
+    }
+
+    fn on_return(&mut self, _pc: usize, _return_pc: usize, _src: &Source, _stack: &[Vobj]) {
+        let mut data = self.data.lock().expect("profile poisoned");
+        data.frames.pop();
+    }
+
+    fn on_unwind(&mut self, _pc: usize, depth: usize, _stack: &[Vobj]) {
+        let mut data = self.data.lock().expect("profile poisoned");
+        data.frames.truncate(depth);
+    }
+}
@@ -0,0 +1,97 @@
+use std::collections::HashMap;
+
+use super::diagnostic::{Diagnostic, DiagnosticKind};
+use super::instruction::{Instruction, Operand};
+use super::vobj::Vobj;
+
+/// Immutable code and debug information, shared between any number of vms
+/// through an `Arc`.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Program {
+    code: Vec<Instruction>,
+    labels: HashMap<usize, String>,
+}
+
+impl Program {
+    pub fn new(code: Vec<Instruction>) -> Self {
+        Self {
+            code,
+            labels: HashMap::new(),
+        }
+    }
+
+    /// Attaches a label name to the instruction at `index`.
+    pub fn with_label(mut self, index: usize, label: &str) -> Self {
+        self.labels.insert(index, label.to_string());
+        self
+    }
+
+    pub fn code(&self) -> &[Instruction] {
+        &self.code
+    }
+
+    pub fn len(&self) -> usize {
+        self.code.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.code.is_empty()
+    }
+
+    pub fn label(&self, index: usize) -> Option<&str> {
+        self.labels.get(&index).map(String::as_str)
+    }
+
+    /// Every literal the program loads, in code order.
+    pub fn constants(&self) -> impl Iterator<Item = &Vobj> {
+        self.code
+            .iter()
+            .filter_map(|instruction| match instruction {
+                Instruction::Load { value, .. } => Some(value),
+                _ => None,
+            })
+    }
+
+    /// Checks every branch target before running, reporting each one the vm
+    /// would reject.
+    pub fn verify(&self) -> Result<(), Vec<Diagnostic>> {
+        let mut diagnostics = Vec::new();
+        for instruction in &self.code {
+            let target = match instruction.operand() {
+                Operand::Index(target) => target,
+                _ => continue,
+            };
+            if target > self.code.len() {
+                diagnostics.push(Diagnostic::new(
+                    DiagnosticKind::Verification,
+                    format!(
+                        "branch target {} is out of range for a program of {} instructions",
+                        target,
+                        self.code.len()
+                    ),
+                    instruction.src().clone(),
+                ));
+            }
+        }
+        if diagnostics.is_empty() {
+            Ok(())
+        } else {
+            Err(diagnostics)
+        }
+    }
+
+    pub(crate) fn extend(&mut self, code: Vec<Instruction>) {
+        self.code.extend(code);
+    }
+
+    pub(crate) fn truncate(&mut self, len: usize) {
+        self.code.truncate(len);
+        self.labels.retain(|index, _| *index < len);
+    }
+}
+
+impl From<Vec<Instruction>> for Program {
+    fn from(code: Vec<Instruction>) -> Self {
+        Self::new(code)
+    }
+}
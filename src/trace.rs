@@ -0,0 +1,210 @@
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+
+use super::asm;
+use super::err::Source;
+use super::instruction::Instruction;
+use super::vobj::Vobj;
+
+/// Hooks invoked by [`crate::vm::Vm`] while it executes. Every method has an
+/// empty default so observers only implement what they need.
+pub trait Observer: Send {
+    fn before_instruction(
+        &mut self,
+        _pc: usize,
+        _instruction: &Instruction,
+        _src: &Source,
+        _stack: &[Vobj],
+    ) {
+    }
+
+    fn after_instruction(
+        &mut self,
+        _pc: usize,
+        _instruction: &Instruction,
+        _src: &Source,
+        _stack: &[Vobj],
+    ) {
+    }
+
+    fn on_error(
+        &mut self,
+        _pc: usize,
+        _instruction: &Instruction,
+        _src: &Source,
+        _stack: &[Vobj],
+        _error: &str,
+    ) {
+    }
+
+    fn on_call(&mut self, _pc: usize, _target: usize, _src: &Source, _stack: &[Vobj]) {}
+
+    fn on_return(&mut self, _pc: usize, _return_pc: usize, _src: &Source, _stack: &[Vobj]) {}
+
+    /// An error at `pc` left every call frame above the outermost `depth`
+    /// without returning.
+    fn on_unwind(&mut self, _pc: usize, _depth: usize, _stack: &[Vobj]) {}
+}
+
+/// Prints one line per executed instruction with the resulting stack.
+pub struct Tracer<W: Write + Send> {
+    out: W,
+}
+
+impl<W: Write + Send> Tracer<W> {
+    pub fn new(out: W) -> Self {
+        Self { out }
+    }
+}
+
+impl Tracer<io::Stderr> {
+    pub fn stderr() -> Self {
+        Self::new(io::stderr())
+    }
+}
+
+impl<W: Write + Send> Observer for Tracer<W> {
+    fn after_instruction(
+        &mut self,
+        pc: usize,
+        instruction: &Instruction,
+        src: &Source,
+        stack: &[Vobj],
+    ) {
+        let values: Vec<String> = stack.iter().map(asm::format_value).collect();
+        // tracing must never abort the program it observes
+        let _ = writeln!(
+            self.out,
+            "{:>4}  {:<24} ; {:<16} [{}]",
+            pc,
+            asm::format_instruction(instruction),
+            src.to_string(),
+            values.join(", ")
+        );
+    }
+
+    fn on_error(
+        &mut self,
+        pc: usize,
+        instruction: &Instruction,
+        src: &Source,
+        _stack: &[Vobj],
+        error: &str,
+    ) {
+        let _ = writeln!(
+            self.out,
+            "{:>4}  {:<24} ; {:<16} error: {}",
+            pc,
+            asm::format_instruction(instruction),
+            src.to_string(),
+            error
+        );
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TraceEvent {
+    Executed {
+        pc: usize,
+        instruction: String,
+        src: String,
+        stack: Vec<Vobj>,
+    },
+    Error {
+        pc: usize,
+        instruction: String,
+        src: String,
+        error: String,
+    },
+    Call {
+        pc: usize,
+        target: usize,
+    },
+    Return {
+        pc: usize,
+        return_pc: usize,
+    },
+    Unwind {
+        pc: usize,
+        depth: usize,
+    },
+}
+
+/// Records every event into a shared `Vec`. Clones share the same record, so
+/// keep one clone to read the trace after handing another to the vm.
+#[derive(Debug, Clone, Default)]
+pub struct TraceCollector {
+    events: Arc<Mutex<Vec<TraceEvent>>>,
+}
+
+impl TraceCollector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn events(&self) -> Vec<TraceEvent> {
+        self.events.lock().expect("trace poisoned").clone()
+    }
+
+    /// The pc of every instruction that completed, in execution order.
+    pub fn executed_pcs(&self) -> Vec<usize> {
+        self.events
+            .lock()
+            .expect("trace poisoned")
+            .iter()
+            .filter_map(|event| match event {
+                TraceEvent::Executed { pc, .. } => Some(*pc),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn record(&self, event: TraceEvent) {
+        self.events.lock().expect("trace poisoned").push(event);
+    }
+}
+
+impl Observer for TraceCollector {
+    fn after_instruction(
+        &mut self,
+        pc: usize,
+        instruction: &Instruction,
+        src: &Source,
+        stack: &[Vobj],
+    ) {
+        self.record(TraceEvent::Executed {
+            pc,
+            instruction: asm::format_instruction(instruction),
+            src: src.to_string(),
+            stack: stack.to_vec(),
+        });
+    }
+
+    fn on_error(
+        &mut self,
+        pc: usize,
+        instruction: &Instruction,
+        src: &Source,
+        _stack: &[Vobj],
+        error: &str,
+    ) {
+        self.record(TraceEvent::Error {
+            pc,
+            instruction: asm::format_instruction(instruction),
+            src: src.to_string(),
+            error: error.to_string(),
+        });
+    }
+
+    fn on_call(&mut self, pc: usize, target: usize, _src: &Source, _stack: &[Vobj]) {
+        self.record(TraceEvent::Call { pc, target });
+    }
+
+    fn on_return(&mut self, pc: usize, return_pc: usize, _src: &Source, _stack: &[Vobj]) {
+        self.record(TraceEvent::Return { pc, return_pc });
+    }
+
+    fn on_unwind(&mut self, pc: usize, depth: usize, _stack: &[Vobj]) {
+        self.record(TraceEvent::Unwind { pc, depth });
+    }
+}
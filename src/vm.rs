@@ -1,273 +1,1378 @@
 use std::cell::{Cell, Ref, RefCell};
+use std::collections::{HashMap, VecDeque};
+use std::io::{self, Write};
+use std::sync::Arc;
+use std::time::Instant;
 use std::vec::Vec;
 
+use super::err::{
+    Backtrace, BacktraceFrame, BranchRecord, ConfigError, Limit, Position, RuntimeError, Source,
+    VMError,
+};
 use super::instruction::Instruction;
-use super::vobj::Vobj;
+use super::native::Natives;
+use super::profile::Profiler;
+use super::program::Program;
+use super::trace::Observer;
+use super::vobj::{Vobj, VobjKind};
 
-pub struct Vm<'a> {
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Frame {
+    pub call_pc: usize,
+    pub target: usize,
+    /// Where `ret` continues: after the call, or back at a `sortby` that
+    /// called its key function.
+    pub return_pc: usize,
+}
+
+// An active try region: where to resume and what to unwind to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Handler {
+    catch_pc: usize,
+    stack_len: usize,
+    frame_depth: usize,
+}
+
+// A `sortby` waiting for its key function to return the key of the next
+// element.
+#[derive(Debug, Clone, PartialEq)]
+struct PendingSort {
+    pc: usize,
+    frame_depth: usize,
+    stack_len: usize,
+    values: Vec<Vobj>,
+    keys: Vec<Vobj>,
+}
+
+/// What [`Vm::rewind`] returns to: the program length, operand stack,
+/// globals, call frames, try regions and unfinished sorts when
+/// [`Vm::snapshot`] was taken.
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    code_len: usize,
+    stack: Vec<Vobj>,
+    globals: HashMap<String, Vobj>,
+    frames: Vec<Frame>,
+    handlers: Vec<Handler>,
+    sorts: Vec<PendingSort>,
+    heap_bytes: usize,
+}
+
+// how many instructions run between two deadline checks
+const DEADLINE_CHECK_INTERVAL: u64 = 1024;
+
+// how many taken branches a runtime error reports
+pub const BRANCH_HISTORY: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RunOutcome {
+    Finished,
+    OutOfFuel,
+    DeadlineExceeded,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VmConfig {
+    pub max_stack_depth: usize,
+    pub max_heap_bytes: usize,
+    pub max_call_depth: usize,
+    /// SQL-like null handling: arithmetic with a null operand yields null
+    /// and ordered comparisons with null never branch, instead of failing.
+    pub null_propagation: bool,
+    /// How many instructions may execute; `None` is unlimited.
+    pub fuel: Option<u64>,
+    pub deadline: Option<Instant>,
+}
+
+impl Default for VmConfig {
+    fn default() -> Self {
+        Self {
+            max_stack_depth: 1 << 20,
+            max_heap_bytes: 64 << 20,
+            max_call_depth: 1 << 12,
+            null_propagation: false,
+            fuel: None,
+            deadline: None,
+        }
+    }
+}
+
+pub struct VmBuilder {
+    program: Arc<Program>,
+    stack: Vec<Vobj>,
+    config: VmConfig,
+    output: Option<Box<dyn Write + Send>>,
+    observers: Vec<Box<dyn Observer>>,
+    natives: Arc<Natives>,
+}
+
+impl VmBuilder {
+    pub fn new(code: Vec<Instruction>) -> Self {
+        Self::from_program(Arc::new(Program::new(code)))
+    }
+
+    pub fn from_program(program: Arc<Program>) -> Self {
+        Self {
+            program,
+            stack: Vec::new(),
+            config: VmConfig::default(),
+            output: None,
+            observers: Vec::new(),
+            natives: Natives::standard(),
+        }
+    }
+
+    /// Values on the operand stack before the first instruction runs.
+    pub fn stack(mut self, stack: Vec<Vobj>) -> Self {
+        self.stack = stack;
+        self
+    }
+
+    pub fn config(mut self, config: VmConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    pub fn max_stack_depth(mut self, max_stack_depth: usize) -> Self {
+        self.config.max_stack_depth = max_stack_depth;
+        self
+    }
+
+    pub fn max_heap_bytes(mut self, max_heap_bytes: usize) -> Self {
+        self.config.max_heap_bytes = max_heap_bytes;
+        self
+    }
+
+    pub fn max_call_depth(mut self, max_call_depth: usize) -> Self {
+        self.config.max_call_depth = max_call_depth;
+        self
+    }
+
+    pub fn fuel(mut self, fuel: u64) -> Self {
+        self.config.fuel = Some(fuel);
+        self
+    }
+
+    pub fn deadline(mut self, deadline: Instant) -> Self {
+        self.config.deadline = Some(deadline);
+        self
+    }
+
+    /// Where `Print` writes; standard output by default.
+    pub fn output(mut self, output: Box<dyn Write + Send>) -> Self {
+        self.output = Some(output);
+        self
+    }
+
+    pub fn observer(mut self, observer: Box<dyn Observer>) -> Self {
+        self.observers.push(observer);
+        self
+    }
+
+    /// Replaces the natives the program can call, the math library by
+    /// default.
+    pub fn natives(mut self, natives: Natives) -> Self {
+        self.natives = Arc::new(natives);
+        self
+    }
+
+    pub fn native(
+        mut self,
+        name: &str,
+        arity: usize,
+        function: impl Fn(&[Vobj]) -> Result<Vobj, VMError> + Send + Sync + 'static,
+    ) -> Self {
+        Arc::make_mut(&mut self.natives).register(name, arity, function);
+        self
+    }
+
+    pub fn build(self) -> Result<Vm, ConfigError> {
+        let config = self.config;
+        if config.max_stack_depth == 0 {
+            return Err(ConfigError::ZeroStackDepth);
+        }
+        if config.max_call_depth == 0 {
+            return Err(ConfigError::ZeroCallDepth);
+        }
+        if self.stack.len() > config.max_stack_depth {
+            return Err(ConfigError::StackTooDeep {
+                len: self.stack.len(),
+                max: config.max_stack_depth,
+            });
+        }
+        let heap_bytes: usize = self.stack.iter().map(Vobj::heap_size).sum();
+        if heap_bytes > config.max_heap_bytes {
+            return Err(ConfigError::HeapTooLarge {
+                bytes: heap_bytes,
+                max: config.max_heap_bytes,
+            });
+        }
+
+        let mut vm = Vm::from_program(self.program, config);
+        vm.heap_bytes.set(heap_bytes);
+        *vm.stack.get_mut() = self.stack.clone();
+        vm.initial_stack = self.stack;
+        if let Some(output) = self.output {
+            *vm.output.get_mut() = output;
+        }
+        *vm.observers.get_mut() = self.observers;
+        vm.natives = self.natives;
+        Ok(vm)
+    }
+}
+
+/// Per-execution state. The program itself is shared, so creating a vm is
+/// cheap and each one can be sent to its own thread.
+pub struct Vm {
     stack: RefCell<Vec<Vobj>>,
-    code: Vec<Instruction<'a>>,
+    program: Arc<Program>,
     pc: Cell<usize>,
+    frames: RefCell<Vec<Frame>>,
+    handlers: RefCell<Vec<Handler>>,
+    sorts: RefCell<Vec<PendingSort>>,
+    observers: RefCell<Vec<Box<dyn Observer>>>,
+    fuel: Cell<Option<u64>>,
+    deadline: Option<Instant>,
+    executed: Cell<u64>,
+    config: VmConfig,
+    heap_bytes: Cell<usize>,
+    output: RefCell<Box<dyn Write + Send>>,
+    globals: RefCell<HashMap<String, Vobj>>,
+    initial_stack: Vec<Vobj>,
+    natives: Arc<Natives>,
+    // (pc, target) of the most recent control transfers
+    branches: RefCell<VecDeque<(usize, usize)>>,
 }
 
-impl<'a> Vm<'a> {
+impl Vm {
     pub fn dump_mem(&self) -> Ref<'_, Vec<Vobj>> {
         self.stack.borrow()
     }
 
-    pub fn load(code: Vec<Instruction<'a>>) -> Self {
+    pub fn load(code: Vec<Instruction>) -> Self {
+        Self::with_config(code, VmConfig::default())
+    }
+
+    pub fn with_config(code: Vec<Instruction>, config: VmConfig) -> Self {
+        Self::from_program(Arc::new(Program::new(code)), config)
+    }
+
+    pub fn new(program: Arc<Program>) -> Self {
+        Self::from_program(program, VmConfig::default())
+    }
+
+    pub fn from_program(program: Arc<Program>, config: VmConfig) -> Self {
         let stack = RefCell::new(Vec::new());
         Self {
             stack,
-            code,
+            program,
             pc: Cell::new(0),
+            frames: RefCell::new(Vec::new()),
+            handlers: RefCell::new(Vec::new()),
+            sorts: RefCell::new(Vec::new()),
+            observers: RefCell::new(Vec::new()),
+            fuel: Cell::new(config.fuel),
+            deadline: config.deadline,
+            executed: Cell::new(0),
+            config,
+            heap_bytes: Cell::new(0),
+            output: RefCell::new(Box::new(io::stdout())),
+            globals: RefCell::new(HashMap::new()),
+            initial_stack: Vec::new(),
+            natives: Natives::standard(),
+            branches: RefCell::new(VecDeque::with_capacity(BRANCH_HISTORY)),
+        }
+    }
+
+    /// Restores the pc, stack, globals, call frames and budget to how the vm
+    /// was built, so the same program can run again.
+    pub fn reset(&mut self) {
+        self.pc.set(0);
+        self.fuel.set(self.config.fuel);
+        self.deadline = self.config.deadline;
+        self.executed.set(0);
+        self.frames.get_mut().clear();
+        self.handlers.get_mut().clear();
+        self.sorts.get_mut().clear();
+        self.globals.get_mut().clear();
+        self.branches.get_mut().clear();
+        *self.stack.get_mut() = self.initial_stack.clone();
+        self.heap_bytes
+            .set(self.initial_stack.iter().map(Vobj::heap_size).sum());
+    }
+
+    /// Resets the vm, pushes `args` and runs the program, returning the final
+    /// stack.
+    pub fn run_with(&mut self, args: Vec<Vobj>) -> Result<Vec<Vobj>, Box<RuntimeError>> {
+        self.reset();
+        for arg in args {
+            self.push(arg).map_err(|error| self.runtime_error(error))?;
         }
+        self.run()?;
+        Ok(self.stack.get_mut().clone())
     }
 
-    pub fn run(&'a self) -> Result<(), String> {
-        let len = self.code.len();
-        while self.pc.get() < len {
-            let result = match &self.code[self.pc.get()] {
-                Instruction::Nop { str_repr, src } => (Ok(()),str_repr,src),
-                Instruction::Load {
-                    value,
-                    str_repr,
-                    src,
-                } => (self.execute_load((*value).clone()), str_repr, src),
-                Instruction::Add { str_repr, src } => (self.execute_add(), str_repr, src),
-                Instruction::Sub { str_repr, src } => (self.execute_sub(), str_repr, src),
-                Instruction::Mul { str_repr, src } => (self.execute_mul(), str_repr, src),
-                Instruction::Div { str_repr, src } => (self.execute_div(), str_repr, src),
-                Instruction::Br {
-                    br_index,
-                    str_repr,
-                    src,
-                } => (self.execute_br(*br_index), str_repr, src),
-                Instruction::Beq {
-                    br_index,
-                    str_repr,
-                    src,
-                } => (self.execute_beq(*br_index), str_repr, src),
-                Instruction::Bnq {
-                    br_index,
-                    str_repr,
-                    src,
-                } => (self.execute_bnq(*br_index), str_repr, src),
-                Instruction::Bg {
-                    br_index,
-                    str_repr,
-                    src,
-                } => (self.execute_bgt(*br_index), str_repr, src),
-                Instruction::Bge {
-                    br_index,
-                    str_repr,
-                    src,
-                } => (self.execute_bge(*br_index), str_repr, src),
-                Instruction::Blt {
-                    br_index,
-                    str_repr,
-                    src,
-                } => (self.execute_blt(*br_index), str_repr, src),
-                Instruction::Ble {
-                    br_index,
-                    str_repr,
-                    src,
-                } => (self.execute_ble(*br_index), str_repr, src),
-                _ => todo!("implement other instructions executions"),
-            };
-            if let (Err(error), str_repr, src) = result {
-                return Err(format!(
-                    "{} -> {} {} ({})",
-                    error,
-                    self.pc.get(),
-                    str_repr,
-                    src
-                ));
-            }
-            self.pc.set(self.pc.get() + 1);
+    /// Resets the vm, binds each `(name, value)` as a global and runs the
+    /// program, returning the final stack.
+    pub fn run_with_globals<'n>(
+        &mut self,
+        globals: impl IntoIterator<Item = (&'n str, Vobj)>,
+    ) -> Result<Vec<Vobj>, Box<RuntimeError>> {
+        self.reset();
+        for (name, value) in globals {
+            self.store_global(name, value)
+                .map_err(|error| self.runtime_error(error))?;
         }
+        self.run()?;
+        Ok(self.stack.get_mut().clone())
+    }
+
+    pub fn global(&self, name: &str) -> Option<Vobj> {
+        self.globals.borrow().get(name).cloned()
+    }
+
+    pub fn set_global(&mut self, name: &str, value: Vobj) -> Result<(), VMError> {
+        self.store_global(name, value)
+    }
+
+    pub fn builder(code: Vec<Instruction>) -> VmBuilder {
+        VmBuilder::new(code)
+    }
+
+    pub fn program(&self) -> &Arc<Program> {
+        &self.program
+    }
+
+    pub fn config(&self) -> &VmConfig {
+        &self.config
+    }
+
+    pub fn natives(&self) -> &Natives {
+        &self.natives
+    }
+
+    /// Makes `function` callable as `native <name>`, replacing any native of
+    /// that name.
+    pub fn register_native(
+        &mut self,
+        name: &str,
+        arity: usize,
+        function: impl Fn(&[Vobj]) -> Result<Vobj, VMError> + Send + Sync + 'static,
+    ) {
+        Arc::make_mut(&mut self.natives).register(name, arity, function);
+    }
+
+    /// Limits how many more instructions may execute; `None` is unlimited.
+    pub fn set_fuel(&mut self, fuel: Option<u64>) {
+        self.fuel.set(fuel);
+    }
+
+    pub fn add_fuel(&mut self, fuel: u64) {
+        if let Some(remaining) = self.fuel.get() {
+            self.fuel.set(Some(remaining.saturating_add(fuel)));
+        }
+    }
+
+    pub fn fuel(&self) -> Option<u64> {
+        self.fuel.get()
+    }
+
+    pub fn set_deadline(&mut self, deadline: Option<Instant>) {
+        self.deadline = deadline;
+    }
+
+    pub fn instructions_executed(&self) -> u64 {
+        self.executed.get()
+    }
+
+    pub fn add_observer(&mut self, observer: Box<dyn Observer>) {
+        self.observers.get_mut().push(observer);
+    }
+
+    /// Registers a [`Profiler`] and returns a handle to read its report.
+    pub fn enable_profiling(&mut self) -> Profiler {
+        let profiler = Profiler::new();
+        self.add_observer(Box::new(profiler.clone()));
+        profiler
+    }
+
+    pub fn frames(&self) -> Ref<'_, Vec<Frame>> {
+        self.frames.borrow()
+    }
+
+    /// The active call frames, innermost first, and the most recent taken
+    /// branches.
+    pub fn backtrace(&self) -> Backtrace {
+        let code = self.program.code();
+        let frames = self
+            .frames
+            .borrow()
+            .iter()
+            .rev()
+            .map(|frame| BacktraceFrame {
+                name: match self.program.label(frame.target) {
+                    Some(label) => label.to_string(),
+                    None => format!("fn@{}", frame.target),
+                },
+                call_pc: frame.call_pc,
+                src: code[frame.call_pc].src().clone(),
+            })
+            .collect();
+        let branches = self
+            .branches
+            .borrow()
+            .iter()
+            .filter_map(|&(pc, target)| {
+                code.get(pc).map(|instruction| BranchRecord {
+                    pc,
+                    target,
+                    src: instruction.src().clone(),
+                })
+            })
+            .collect();
+        Backtrace { frames, branches }
+    }
+
+    /// Adds instructions to the end of the program. A program shared with
+    /// other vms is copied first, so they are unaffected.
+    pub fn append(&mut self, code: Vec<Instruction>) {
+        Arc::make_mut(&mut self.program).extend(code);
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            code_len: self.program.len(),
+            stack: self.stack.borrow().clone(),
+            globals: self.globals.borrow().clone(),
+            frames: self.frames.borrow().clone(),
+            handlers: self.handlers.borrow().clone(),
+            sorts: self.sorts.borrow().clone(),
+            heap_bytes: self.heap_bytes.get(),
+        }
+    }
+
+    /// Drops every instruction appended since `snapshot` was taken and
+    /// restores the state it holds, leaving the vm positioned to execute
+    /// whatever is appended next.
+    pub fn rewind(&mut self, snapshot: Snapshot) {
+        Arc::make_mut(&mut self.program).truncate(snapshot.code_len);
+        self.pc.set(self.program.len());
+        self.branches.get_mut().clear();
+        *self.stack.get_mut() = snapshot.stack;
+        *self.globals.get_mut() = snapshot.globals;
+        *self.frames.get_mut() = snapshot.frames;
+        *self.handlers.get_mut() = snapshot.handlers;
+        *self.sorts.get_mut() = snapshot.sorts;
+        self.heap_bytes.set(snapshot.heap_bytes);
+    }
+
+    pub fn pc(&self) -> usize {
+        self.pc.get()
+    }
+
+    pub fn code(&self) -> &[Instruction] {
+        self.program.code()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.pc.get() >= self.program.len()
+    }
+
+    pub fn run(&self) -> Result<(), Box<RuntimeError>> {
+        match self.run_budgeted()? {
+            RunOutcome::Finished => Ok(()),
+            RunOutcome::OutOfFuel => Err(self.runtime_error(VMError::OutOfFuelErr)),
+            RunOutcome::DeadlineExceeded => Err(self.runtime_error(VMError::DeadlineExceededErr)),
+        }
+    }
+
+    /// Runs until the program ends or its budget runs out. Running out leaves
+    /// the vm ready to resume from the next instruction once refueled.
+    pub fn run_budgeted(&self) -> Result<RunOutcome, Box<RuntimeError>> {
+        while !self.is_finished() {
+            if self.fuel.get() == Some(0) {
+                return Ok(RunOutcome::OutOfFuel);
+            }
+            if let Some(deadline) = self.deadline {
+                let checkpoint = self.executed.get().is_multiple_of(DEADLINE_CHECK_INTERVAL);
+                if checkpoint && Instant::now() >= deadline {
+                    return Ok(RunOutcome::DeadlineExceeded);
+                }
+            }
+            self.step()?;
+        }
+        Ok(RunOutcome::Finished)
+    }
+
+    /// Executes the instruction at the pc. Stepping a finished program does
+    /// nothing.
+    pub fn step(&self) -> Result<(), Box<RuntimeError>> {
+        if self.is_finished() {
+            return Ok(());
+        }
+        let pc = self.pc.get();
+        let instruction = &self.program.code()[pc];
+        self.notify(|observer, stack| {
+            observer.before_instruction(pc, instruction, instruction.src(), stack)
+        });
+        let result = match instruction {
+            Instruction::Nop { str_repr, src } => (Ok(()), str_repr, src),
+            Instruction::Load {
+                value,
+                str_repr,
+                src,
+            } => (self.execute_load((*value).clone()), str_repr, src),
+            Instruction::Add { str_repr, src } => (self.execute_add(), str_repr, src),
+            Instruction::Sub { str_repr, src } => (self.execute_sub(), str_repr, src),
+            Instruction::Mul { str_repr, src } => (self.execute_mul(), str_repr, src),
+            Instruction::Div { str_repr, src } => (self.execute_div(), str_repr, src),
+            Instruction::Br {
+                br_index,
+                str_repr,
+                src,
+            } => (self.execute_br(*br_index), str_repr, src),
+            Instruction::Beq {
+                br_index,
+                str_repr,
+                src,
+            } => (self.execute_beq(*br_index), str_repr, src),
+            Instruction::Bnq {
+                br_index,
+                str_repr,
+                src,
+            } => (self.execute_bnq(*br_index), str_repr, src),
+            Instruction::Bg {
+                br_index,
+                str_repr,
+                src,
+            }
+            | Instruction::Bgt {
+                br_index,
+                str_repr,
+                src,
+            } => (self.execute_bgt(*br_index), str_repr, src),
+            Instruction::Bge {
+                br_index,
+                str_repr,
+                src,
+            } => (self.execute_bge(*br_index), str_repr, src),
+            Instruction::Blt {
+                br_index,
+                str_repr,
+                src,
+            } => (self.execute_blt(*br_index), str_repr, src),
+            Instruction::Ble {
+                br_index,
+                str_repr,
+                src,
+            } => (self.execute_ble(*br_index), str_repr, src),
+            Instruction::Call {
+                br_index,
+                str_repr,
+                src,
+            } => (self.execute_call(*br_index, src), str_repr, src),
+            Instruction::Ret { str_repr, src } => (self.execute_ret(src), str_repr, src),
+            Instruction::Print { str_repr, src } => (self.execute_print(), str_repr, src),
+            Instruction::GLoad {
+                name,
+                str_repr,
+                src,
+            } => (self.execute_gload(name), str_repr, src),
+            Instruction::GStore {
+                name,
+                str_repr,
+                src,
+            } => (self.execute_gstore(name), str_repr, src),
+            Instruction::Throw { str_repr, src } => (self.execute_throw(), str_repr, src),
+            Instruction::TryStart {
+                br_index,
+                str_repr,
+                src,
+            } => (self.execute_try_start(*br_index), str_repr, src),
+            Instruction::TryEnd { str_repr, src } => (self.execute_try_end(), str_repr, src),
+            Instruction::Bnull {
+                br_index,
+                str_repr,
+                src,
+            } => (self.execute_bnull(*br_index, true), str_repr, src),
+            Instruction::Bnnull {
+                br_index,
+                str_repr,
+                src,
+            } => (self.execute_bnull(*br_index, false), str_repr, src),
+            Instruction::Coalesce { str_repr, src } => (self.execute_coalesce(), str_repr, src),
+            Instruction::Cmp { str_repr, src } => (
+                self.execute_binary(|value1, value2| Ok(Vobj::Int(value1.compare(value2) as i64))),
+                str_repr,
+                src,
+            ),
+            Instruction::Rem { str_repr, src } => {
+                (self.execute_propagating_binary(Vobj::rem), str_repr, src)
+            }
+            Instruction::Mod { str_repr, src } => {
+                (self.execute_propagating_binary(Vobj::modulo), str_repr, src)
+            }
+            Instruction::Pow { str_repr, src } => {
+                (self.execute_propagating_binary(Vobj::pow), str_repr, src)
+            }
+            Instruction::FloorDiv { str_repr, src } => (
+                self.execute_propagating_binary(Vobj::floor_div),
+                str_repr,
+                src,
+            ),
+            Instruction::TrueDiv { str_repr, src } => (
+                self.execute_propagating_binary(Vobj::true_div),
+                str_repr,
+                src,
+            ),
+            Instruction::Neg { str_repr, src } => {
+                (self.execute_propagating_unary(Vobj::neg), str_repr, src)
+            }
+            Instruction::Abs { str_repr, src } => {
+                (self.execute_propagating_unary(Vobj::abs), str_repr, src)
+            }
+            Instruction::And { str_repr, src } => (
+                self.execute_propagating_binary(Vobj::bit_and),
+                str_repr,
+                src,
+            ),
+            Instruction::Or { str_repr, src } => {
+                (self.execute_propagating_binary(Vobj::bit_or), str_repr, src)
+            }
+            Instruction::Xor { str_repr, src } => (
+                self.execute_propagating_binary(Vobj::bit_xor),
+                str_repr,
+                src,
+            ),
+            Instruction::Not { str_repr, src } => {
+                (self.execute_propagating_unary(Vobj::bit_not), str_repr, src)
+            }
+            Instruction::Shl { str_repr, src } => {
+                (self.execute_propagating_binary(Vobj::shl), str_repr, src)
+            }
+            Instruction::Sar { str_repr, src } => {
+                (self.execute_propagating_binary(Vobj::sar), str_repr, src)
+            }
+            Instruction::Shr { str_repr, src } => {
+                (self.execute_propagating_binary(Vobj::shr), str_repr, src)
+            }
+            Instruction::Native {
+                name,
+                str_repr,
+                src,
+            } => (self.execute_native(name), str_repr, src),
+            Instruction::Popcount { str_repr, src } => (
+                self.execute_propagating_unary(Vobj::popcount),
+                str_repr,
+                src,
+            ),
+            Instruction::ToInt { str_repr, src } => {
+                (self.execute_propagating_unary(Vobj::to_int), str_repr, src)
+            }
+            Instruction::ToDouble { str_repr, src } => (
+                self.execute_propagating_unary(Vobj::to_double),
+                str_repr,
+                src,
+            ),
+            Instruction::ToStr { str_repr, src } => {
+                (self.execute_propagating_unary(Vobj::to_str), str_repr, src)
+            }
+            Instruction::ToBool { str_repr, src } => {
+                (self.execute_propagating_unary(Vobj::to_bool), str_repr, src)
+            }
+            Instruction::ParseNum { str_repr, src } => (
+                self.execute_propagating_unary(Vobj::parse_num),
+                str_repr,
+                src,
+            ),
+            Instruction::TypeOf { str_repr, src } => (
+                self.execute_inspect(|value| Vobj::from(value.type_of())),
+                str_repr,
+                src,
+            ),
+            Instruction::IsInt { str_repr, src } => (self.execute_is(VobjKind::Int), str_repr, src),
+            Instruction::IsDouble { str_repr, src } => {
+                (self.execute_is(VobjKind::Double), str_repr, src)
+            }
+            Instruction::IsStr { str_repr, src } => (self.execute_is(VobjKind::Str), str_repr, src),
+            Instruction::IsBool { str_repr, src } => {
+                (self.execute_is(VobjKind::Bool), str_repr, src)
+            }
+            Instruction::IsNull { str_repr, src } => {
+                (self.execute_is(VobjKind::Null), str_repr, src)
+            }
+            Instruction::IsError { str_repr, src } => {
+                (self.execute_is(VobjKind::Error), str_repr, src)
+            }
+            Instruction::AssertType {
+                kind,
+                str_repr,
+                src,
+            } => (self.execute_assert_type(*kind), str_repr, src),
+            Instruction::MakeList { str_repr, src } => (self.execute_make_list(), str_repr, src),
+            Instruction::Len { str_repr, src } => (self.execute_unary(Vobj::len), str_repr, src),
+            Instruction::Get { str_repr, src } => (self.execute_binary(Vobj::get), str_repr, src),
+            Instruction::Sort { str_repr, src } => (self.execute_unary(Vobj::sort), str_repr, src),
+            Instruction::SortBy {
+                br_index,
+                str_repr,
+                src,
+            } => (self.execute_sort_by(*br_index, src), str_repr, src),
+            Instruction::Reverse { str_repr, src } => {
+                (self.execute_unary(Vobj::reverse), str_repr, src)
+            }
+            Instruction::Contains { str_repr, src } => {
+                (self.execute_binary(Vobj::contains), str_repr, src)
+            }
+            Instruction::IndexOf { str_repr, src } => {
+                (self.execute_binary(Vobj::index_of), str_repr, src)
+            }
+            Instruction::Min { str_repr, src } => {
+                (self.execute_unary(Vobj::minimum), str_repr, src)
+            }
+            Instruction::Max { str_repr, src } => {
+                (self.execute_unary(Vobj::maximum), str_repr, src)
+            }
+            Instruction::Sum { str_repr, src } => (self.execute_unary(Vobj::sum), str_repr, src),
+            Instruction::Join { str_repr, src } => (self.execute_binary(Vobj::join), str_repr, src),
+            Instruction::IterNew { str_repr, src } => {
+                (self.execute_unary(Vobj::iter), str_repr, src)
+            }
+            Instruction::IterNext {
+                br_index,
+                str_repr,
+                src,
+            } => (self.execute_iter_next(*br_index), str_repr, src),
+            Instruction::Range { str_repr, src } => {
+                (self.execute_binary(Vobj::range), str_repr, src)
+            }
+            Instruction::RangeStep { str_repr, src } => (self.execute_range_step(), str_repr, src),
+        };
+        match result {
+            (Err(error), _, src) => {
+                // observers see every error, including the ones a handler catches
+                let message = error.to_string();
+                self.notify(|observer, stack| {
+                    observer.on_error(pc, instruction, src, stack, &message)
+                });
+                if let Err(error) = self.unwind(error) {
+                    return Err(self.runtime_error(error));
+                }
+            }
+            (Ok(()), _, src) => self
+                .notify(|observer, stack| observer.after_instruction(pc, instruction, src, stack)),
+        }
+        if let Some(fuel) = self.fuel.get() {
+            self.fuel.set(Some(fuel.saturating_sub(1)));
+        }
+        self.executed.set(self.executed.get() + 1);
+        self.pc.set(self.pc.get().wrapping_add(1));
         Ok(())
     }
 
-    fn execute_load(&self, value: Vobj) -> Result<(), String> {
-        self.stack.borrow_mut().push(value);
+    fn notify(&self, mut event: impl FnMut(&mut dyn Observer, &[Vobj])) {
+        let mut observers = self.observers.borrow_mut();
+        if observers.is_empty() {
+            return;
+        }
+        let stack = self.stack.borrow();
+        for observer in observers.iter_mut() {
+            event(observer.as_mut(), &stack);
+        }
+    }
+
+    fn runtime_error(&self, error: VMError) -> Box<RuntimeError> {
+        let pc = self.pc.get();
+        let (instruction, src) = match self.program.code().get(pc) {
+            Some(instruction) => (
+                instruction.str_repr().to_string(),
+                instruction.src().clone(),
+            ),
+            None => (String::new(), Source::new("<unknown>", 0)),
+        };
+        Box::new(RuntimeError {
+            error,
+            pc,
+            instruction,
+            src,
+            stack: self.stack.borrow().clone(),
+            backtrace: self.backtrace(),
+        })
+    }
+
+    // Hands the error to the innermost handler as a value, or gives it back
+    // when nothing catches it.
+    fn unwind(&self, error: VMError) -> Result<(), VMError> {
+        let handler = match self.handlers.borrow_mut().pop() {
+            Some(handler) => handler,
+            None => return Err(error),
+        };
+        while self.stack.borrow().len() > handler.stack_len {
+            self.pop();
+        }
+        self.drop_frames(handler.frame_depth);
+        self.push(Vobj::from(error))?;
+        self.jump(handler.catch_pc)
+    }
+
+    // Leaves only the outermost `depth` call frames, telling observers about
+    // the ones that end without returning.
+    fn drop_frames(&self, depth: usize) {
+        if self.frames.borrow().len() <= depth {
+            return;
+        }
+        self.frames.borrow_mut().truncate(depth);
+        // a sort whose key function was dropped will never resume
+        self.sorts
+            .borrow_mut()
+            .retain(|sort| sort.frame_depth < depth);
+        let pc = self.pc.get();
+        self.notify(|observer, stack| observer.on_unwind(pc, depth, stack));
+    }
+
+    // Every branch may target any instruction or one past the last, which
+    // ends the program.
+    fn check_target(&self, br_index: usize) -> Result<(), VMError> {
+        if br_index > self.program.len() {
+            return Err(VMError::InvalidBranchErr(br_index));
+        }
+        Ok(())
+    }
+
+    // step() advances the pc after every instruction, so land one short of the target
+    fn jump(&self, br_index: usize) -> Result<(), VMError> {
+        self.check_target(br_index)?;
+        let mut branches = self.branches.borrow_mut();
+        if branches.len() == BRANCH_HISTORY {
+            branches.pop_front();
+        }
+        branches.push_back((self.pc.get(), br_index));
+        self.pc.set(br_index.wrapping_sub(1));
+        Ok(())
+    }
+
+    fn push(&self, value: Vobj) -> Result<(), VMError> {
+        let mut stack = self.stack.borrow_mut();
+        if stack.len() >= self.config.max_stack_depth {
+            return Err(VMError::LimitExceededErr(Limit::StackDepth(
+                self.config.max_stack_depth,
+            )));
+        }
+        let heap_bytes = self.heap_bytes.get() + value.heap_size();
+        if heap_bytes > self.config.max_heap_bytes {
+            return Err(VMError::LimitExceededErr(Limit::HeapBytes(
+                self.config.max_heap_bytes,
+            )));
+        }
+        self.heap_bytes.set(heap_bytes);
+        stack.push(value);
         Ok(())
     }
 
-    fn execute_add(&self) -> Result<(), String> {
+    fn pop(&self) -> Vobj {
+        let value = self.stack.borrow_mut().pop().expect("empty runtime stack");
+        self.heap_bytes
+            .set(self.heap_bytes.get() - value.heap_size());
+        value
+    }
+
+    fn execute_load(&self, value: Vobj) -> Result<(), VMError> {
+        self.push(value)
+    }
+
+    fn execute_add(&self) -> Result<(), VMError> {
         if self.stack.borrow().len() < 2 {
-            return Err("at least 2 argument are required".to_string());
+            return Err(VMError::StackUnderflowErr(2));
+        }
+        let value2 = self.pop();
+        let value1 = self.pop();
+        if self.propagates_null(&[&value1, &value2]) {
+            return self.push(Vobj::Null);
         }
-        let value2 = self.stack.borrow_mut().pop().expect("empty runtime stack");
-        let value1 = self.stack.borrow_mut().pop().expect("empty runtime stack");
         let sum = Vobj::add(&value1, &value2);
         match sum {
-            Ok(result) => {
-                self.stack.borrow_mut().push(result);
-                Ok(())
-            }
-            Err(error) => Err(error.into()),
+            Ok(result) => self.push(result),
+            Err(error) => Err(error),
         }
     }
 
-    fn execute_sub(&self) -> Result<(), String> {
+    fn execute_sub(&self) -> Result<(), VMError> {
         if self.stack.borrow().len() < 2 {
-            return Err("at least 2 argument are required".to_string());
+            return Err(VMError::StackUnderflowErr(2));
+        }
+        let value2 = self.pop();
+        let value1 = self.pop();
+        if self.propagates_null(&[&value1, &value2]) {
+            return self.push(Vobj::Null);
         }
-        let value2 = self.stack.borrow_mut().pop().expect("empty runtime stack");
-        let value1 = self.stack.borrow_mut().pop().expect("empty runtime stack");
         let sum = Vobj::sub(&value1, &value2);
         match sum {
-            Ok(result) => {
-                self.stack.borrow_mut().push(result);
-                Ok(())
-            }
-            Err(error) => Err(error.into()),
+            Ok(result) => self.push(result),
+            Err(error) => Err(error),
         }
     }
 
-    fn execute_mul(&self) -> Result<(), String> {
+    fn execute_mul(&self) -> Result<(), VMError> {
         if self.stack.borrow().len() < 2 {
-            return Err("at least 2 argument are required".to_string());
+            return Err(VMError::StackUnderflowErr(2));
+        }
+        let value2 = self.pop();
+        let value1 = self.pop();
+        if self.propagates_null(&[&value1, &value2]) {
+            return self.push(Vobj::Null);
         }
-        let value2 = self.stack.borrow_mut().pop().expect("empty runtime stack");
-        let value1 = self.stack.borrow_mut().pop().expect("empty runtime stack");
         let sum = Vobj::mul(&value1, &value2);
         match sum {
-            Ok(result) => {
-                self.stack.borrow_mut().push(result);
-                Ok(())
-            }
-            Err(error) => Err(error.into()),
+            Ok(result) => self.push(result),
+            Err(error) => Err(error),
         }
     }
 
-    fn execute_div(&self) -> Result<(), String> {
+    fn execute_div(&self) -> Result<(), VMError> {
         if self.stack.borrow().len() < 2 {
-            return Err("at least 2 argument are required".to_string());
+            return Err(VMError::StackUnderflowErr(2));
+        }
+        let value2 = self.pop();
+        let value1 = self.pop();
+        if self.propagates_null(&[&value1, &value2]) {
+            return self.push(Vobj::Null);
         }
-        let value2 = self.stack.borrow_mut().pop().expect("empty runtime stack");
-        let value1 = self.stack.borrow_mut().pop().expect("empty runtime stack");
         let sum = Vobj::div(&value1, &value2);
         match sum {
-            Ok(result) => {
-                self.stack.borrow_mut().push(result);
-                Ok(())
-            }
-            Err(error) => Err(error.into()),
+            Ok(result) => self.push(result),
+            Err(error) => Err(error),
+        }
+    }
+
+    fn execute_binary(
+        &self,
+        operation: fn(&Vobj, &Vobj) -> Result<Vobj, VMError>,
+    ) -> Result<(), VMError> {
+        if self.stack.borrow().len() < 2 {
+            return Err(VMError::StackUnderflowErr(2));
         }
+        let value2 = self.pop();
+        let value1 = self.pop();
+        self.push(operation(&value1, &value2)?)
     }
 
-    fn execute_br(&self, br_index: usize) -> Result<(), String> {
-        if br_index >= self.code.len() {
-            return Err("invalid instruction index: out of bound".to_owned());
+    fn execute_unary(&self, operation: fn(&Vobj) -> Result<Vobj, VMError>) -> Result<(), VMError> {
+        if self.stack.borrow().is_empty() {
+            return Err(VMError::StackUnderflowErr(1));
         }
+        let value = self.pop();
+        self.push(operation(&value)?)
+    }
 
-        self.pc.set(br_index - 1);
+    // Like `execute_binary`, but with null propagation a null operand gives
+    // null. Only arithmetic, bitwise and conversion instructions propagate.
+    fn execute_propagating_binary(
+        &self,
+        operation: fn(&Vobj, &Vobj) -> Result<Vobj, VMError>,
+    ) -> Result<(), VMError> {
+        if self.stack.borrow().len() < 2 {
+            return Err(VMError::StackUnderflowErr(2));
+        }
+        let value2 = self.pop();
+        let value1 = self.pop();
+        if self.propagates_null(&[&value1, &value2]) {
+            return self.push(Vobj::Null);
+        }
+        self.push(operation(&value1, &value2)?)
+    }
+
+    fn execute_propagating_unary(
+        &self,
+        operation: fn(&Vobj) -> Result<Vobj, VMError>,
+    ) -> Result<(), VMError> {
+        if self.stack.borrow().is_empty() {
+            return Err(VMError::StackUnderflowErr(1));
+        }
+        let value = self.pop();
+        if self.propagates_null(&[&value]) {
+            return self.push(Vobj::Null);
+        }
+        self.push(operation(&value)?)
+    }
+
+    fn propagates_null(&self, operands: &[&Vobj]) -> bool {
+        self.config.null_propagation && operands.iter().any(|value| matches!(value, Vobj::Null))
+    }
+
+    fn execute_bnull(&self, br_index: usize, null: bool) -> Result<(), VMError> {
+        if self.stack.borrow().is_empty() {
+            return Err(VMError::StackUnderflowErr(1));
+        }
+        if matches!(self.pop(), Vobj::Null) == null {
+            self.jump(br_index)?;
+        }
         Ok(())
     }
 
-    fn execute_beq(&self, br_index: usize) -> Result<(), String> {
-        if br_index >= self.code.len() {
-            return Err("invalid instruction index: out of bound".to_owned());
+    // The value under the top unless it is null, the top otherwise.
+    fn execute_coalesce(&self) -> Result<(), VMError> {
+        if self.stack.borrow().len() < 2 {
+            return Err(VMError::StackUnderflowErr(2));
+        }
+        let fallback = self.pop();
+        let value = self.pop();
+        match value {
+            Vobj::Null => self.push(fallback),
+            value => self.push(value),
+        }
+    }
+
+    // Pushes what `inspect` makes of the top of the stack, leaving the value
+    // itself in place.
+    fn execute_inspect(&self, inspect: impl Fn(&Vobj) -> Vobj) -> Result<(), VMError> {
+        let result = match self.stack.borrow().last() {
+            Some(value) => inspect(value),
+            None => return Err(VMError::StackUnderflowErr(1)),
+        };
+        self.push(result)
+    }
+
+    fn execute_is(&self, kind: VobjKind) -> Result<(), VMError> {
+        self.execute_inspect(|value| Vobj::Bool(value.kind() == kind))
+    }
+
+    fn execute_assert_type(&self, kind: VobjKind) -> Result<(), VMError> {
+        match self.stack.borrow().last() {
+            Some(value) if value.kind() == kind => Ok(()),
+            Some(value) => Err(VMError::TypeErr {
+                operation: "AssertType",
+                position: Position::Only,
+                expected: vec![kind],
+                actual: value.kind(),
+            }),
+            None => Err(VMError::StackUnderflowErr(1)),
+        }
+    }
+
+    // Pops a count and then that many values, pushing them as one list in
+    // the order they were pushed.
+    fn execute_make_list(&self) -> Result<(), VMError> {
+        let count = match self.stack.borrow().last() {
+            Some(Vobj::Int(count)) => *count,
+            Some(other) => {
+                return Err(VMError::TypeErr {
+                    operation: "MakeList",
+                    position: Position::Only,
+                    expected: vec![VobjKind::Int],
+                    actual: other.kind(),
+                })
+            }
+            None => return Err(VMError::StackUnderflowErr(1)),
+        };
+        let count = usize::try_from(count).map_err(|_| {
+            VMError::IncorrectArgumentErr(format!("MakeList: negative count {}", count))
+        })?;
+        if self.stack.borrow().len() <= count {
+            return Err(VMError::StackUnderflowErr(count.saturating_add(1)));
+        }
+        self.pop();
+        let mut values: Vec<Vobj> = (0..count).map(|_| self.pop()).collect();
+        values.reverse();
+        self.push(Vobj::List(values))
+    }
+
+    // Sorts the list on top of the stack by the keys the subroutine at
+    // `br_index` returns for each element. Each key call returns to the
+    // `sortby`, which collects the key and calls again for the next element,
+    // so the key function runs from the main loop like any other call.
+    fn execute_sort_by(&self, br_index: usize, src: &Source) -> Result<(), VMError> {
+        let pc = self.pc.get();
+        let depth = self.frames.borrow().len();
+        let pending = {
+            let mut sorts = self.sorts.borrow_mut();
+            match sorts.last() {
+                Some(sort) if sort.pc == pc && sort.frame_depth == depth => sorts.pop(),
+                _ => None,
+            }
+        };
+        let sort = match pending {
+            Some(mut sort) => {
+                if self.stack.borrow().len() <= sort.stack_len {
+                    return Err(VMError::StackUnderflowErr(1));
+                }
+                let key = self.pop();
+                while self.stack.borrow().len() > sort.stack_len {
+                    self.pop();
+                }
+                sort.keys.push(key);
+                sort
+            }
+            None => {
+                let values = match self.stack.borrow().last() {
+                    Some(Vobj::List(values)) => values.clone(),
+                    Some(other) => {
+                        return Err(VMError::TypeErr {
+                            operation: "SortBy",
+                            position: Position::Only,
+                            expected: vec![VobjKind::List],
+                            actual: other.kind(),
+                        })
+                    }
+                    None => return Err(VMError::StackUnderflowErr(1)),
+                };
+                PendingSort {
+                    pc,
+                    frame_depth: depth,
+                    stack_len: self.stack.borrow().len(),
+                    keys: Vec::with_capacity(values.len()),
+                    values,
+                }
+            }
+        };
+        if let Some(value) = sort.values.get(sort.keys.len()) {
+            self.push(value.clone())?;
+            if let Err(error) = self.call(br_index, pc, src) {
+                self.pop();
+                return Err(error);
+            }
+            self.sorts.borrow_mut().push(sort);
+            return Ok(());
         }
+        let mut keyed: Vec<(Vobj, Vobj)> = sort.keys.into_iter().zip(sort.values).collect();
+        keyed.sort_by(|(key1, _), (key2, _)| key1.cmp(key2));
+        self.pop();
+        self.push(Vobj::List(
+            keyed.into_iter().map(|(_, value)| value).collect(),
+        ))
+    }
+
+    fn execute_range_step(&self) -> Result<(), VMError> {
+        if self.stack.borrow().len() < 3 {
+            return Err(VMError::StackUnderflowErr(3));
+        }
+        let step = self.pop();
+        let stop = self.pop();
+        let start = self.pop();
+        self.push(Vobj::range_step(&start, &stop, &step)?)
+    }
+
+    // Pushes the next element of the iterator on top of the stack, or pops the
+    // exhausted iterator and leaves the loop at `br_index`.
+    fn execute_iter_next(&self, br_index: usize) -> Result<(), VMError> {
+        let next = match self.stack.borrow_mut().last_mut() {
+            Some(Vobj::Iter(state)) => state.next(),
+            Some(other) => {
+                return Err(VMError::TypeErr {
+                    operation: "IterNext",
+                    position: Position::Only,
+                    expected: vec![VobjKind::Iter],
+                    actual: other.kind(),
+                })
+            }
+            None => return Err(VMError::StackUnderflowErr(1)),
+        };
+        match next {
+            Some(value) => self.push(value),
+            None => {
+                self.pop();
+                self.jump(br_index)
+            }
+        }
+    }
+
+    fn execute_br(&self, br_index: usize) -> Result<(), VMError> {
+        self.jump(br_index)
+    }
 
+    fn execute_beq(&self, br_index: usize) -> Result<(), VMError> {
         if self.stack.borrow().len() < 2 {
-            return Err("at least 2 argument are required".to_string());
+            return Err(VMError::StackUnderflowErr(2));
         }
-        let value2 = self.stack.borrow_mut().pop().expect("empty runtime stack");
-        let value1 = self.stack.borrow_mut().pop().expect("empty runtime stack");
+        let value2 = self.pop();
+        let value1 = self.pop();
         if value1 == value2 {
-            self.pc.set(br_index - 1);
+            self.jump(br_index)?;
         }
         Ok(())
     }
 
-    fn execute_bnq(&self, br_index: usize) -> Result<(), String> {
-        if br_index >= self.code.len() {
-            return Err("invalid instruction index: out of bound".to_owned());
-        }
-
+    fn execute_bnq(&self, br_index: usize) -> Result<(), VMError> {
         if self.stack.borrow().len() < 2 {
-            return Err("at least 2 argument are required".to_string());
+            return Err(VMError::StackUnderflowErr(2));
         }
-        let value2 = self.stack.borrow_mut().pop().expect("empty runtime stack");
-        let value1 = self.stack.borrow_mut().pop().expect("empty runtime stack");
+        let value2 = self.pop();
+        let value1 = self.pop();
         if value1 != value2 {
-            self.pc.set(br_index - 1);
+            self.jump(br_index)?;
         }
         Ok(())
     }
 
-    fn execute_bgt(&self, br_index: usize) -> Result<(), String> {
+    fn execute_bgt(&self, br_index: usize) -> Result<(), VMError> {
         if self.stack.borrow().len() < 2 {
-            return Err("at least 2 argument are required".to_string());
+            return Err(VMError::StackUnderflowErr(2));
         }
-        let value2 = self.stack.borrow_mut().pop().expect("empty runtime stack");
-        let value1 = self.stack.borrow_mut().pop().expect("empty runtime stack");
-        let sum = Vobj::greater_than(&value1, &value2);
-        match sum {
-            Ok(result) => {
-                if result {
-                    self.pc.set(br_index - 1);
-                }
-                Ok(())
-            }
-            Err(error) => Err(error.into()),
+        let value2 = self.pop();
+        let value1 = self.pop();
+        if self.propagates_null(&[&value1, &value2]) {
+            return Ok(());
         }
+        if value1 > value2 {
+            self.jump(br_index)?;
+        }
+        Ok(())
     }
 
-    fn execute_bge(&self, br_index: usize) -> Result<(), String> {
+    fn execute_bge(&self, br_index: usize) -> Result<(), VMError> {
         if self.stack.borrow().len() < 2 {
-            return Err("at least 2 argument are required".to_string());
+            return Err(VMError::StackUnderflowErr(2));
         }
-        let value2 = self.stack.borrow_mut().pop().expect("empty runtime stack");
-        let value1 = self.stack.borrow_mut().pop().expect("empty runtime stack");
-        let sum = Vobj::greater_eq(&value1, &value2);
-        match sum {
-            Ok(result) => {
-                if result {
-                    self.pc.set(br_index - 1);
-                }
-                Ok(())
-            }
-            Err(error) => Err(error.into()),
+        let value2 = self.pop();
+        let value1 = self.pop();
+        if self.propagates_null(&[&value1, &value2]) {
+            return Ok(());
+        }
+        if value1 >= value2 {
+            self.jump(br_index)?;
         }
+        Ok(())
     }
 
-    fn execute_blt(&self, br_index: usize) -> Result<(), String> {
+    fn execute_blt(&self, br_index: usize) -> Result<(), VMError> {
         if self.stack.borrow().len() < 2 {
-            return Err("at least 2 argument are required".to_string());
+            return Err(VMError::StackUnderflowErr(2));
         }
-        let value2 = self.stack.borrow_mut().pop().expect("empty runtime stack");
-        let value1 = self.stack.borrow_mut().pop().expect("empty runtime stack");
-        let sum = Vobj::less_than(&value1, &value2);
-        match sum {
-            Ok(result) => {
-                if result {
-                    self.pc.set(br_index - 1);
-                }
-                Ok(())
-            }
-            Err(error) => Err(error.into()),
+        let value2 = self.pop();
+        let value1 = self.pop();
+        if self.propagates_null(&[&value1, &value2]) {
+            return Ok(());
+        }
+        if value1 < value2 {
+            self.jump(br_index)?;
         }
+        Ok(())
     }
 
-    fn execute_ble(&self, br_index: usize) -> Result<(), String> {
+    fn execute_ble(&self, br_index: usize) -> Result<(), VMError> {
         if self.stack.borrow().len() < 2 {
-            return Err("at least 2 argument are required".to_string());
+            return Err(VMError::StackUnderflowErr(2));
         }
-        let value2 = self.stack.borrow_mut().pop().expect("empty runtime stack");
-        let value1 = self.stack.borrow_mut().pop().expect("empty runtime stack");
-        let sum = Vobj::less_eq(&value1, &value2);
-        match sum {
-            Ok(result) => {
-                if result {
-                    self.pc.set(br_index - 1);
-                }
-                Ok(())
-            }
-            Err(error) => Err(error.into()),
+        let value2 = self.pop();
+        let value1 = self.pop();
+        if self.propagates_null(&[&value1, &value2]) {
+            return Ok(());
+        }
+        if value1 <= value2 {
+            self.jump(br_index)?;
+        }
+        Ok(())
+    }
+
+    fn execute_call(&self, br_index: usize, src: &Source) -> Result<(), VMError> {
+        self.call(br_index, self.pc.get() + 1, src)
+    }
+
+    fn call(&self, br_index: usize, return_pc: usize, src: &Source) -> Result<(), VMError> {
+        if self.frames.borrow().len() >= self.config.max_call_depth {
+            return Err(VMError::LimitExceededErr(Limit::CallDepth(
+                self.config.max_call_depth,
+            )));
+        }
+
+        let call_pc = self.pc.get();
+        self.jump(br_index)?;
+        self.frames.borrow_mut().push(Frame {
+            call_pc,
+            target: br_index,
+            return_pc,
+        });
+        self.notify(|observer, stack| observer.on_call(call_pc, br_index, src, stack));
+        Ok(())
+    }
+
+    fn execute_ret(&self, src: &Source) -> Result<(), VMError> {
+        let frame = match self.frames.borrow_mut().pop() {
+            Some(frame) => frame,
+            None => return Err(VMError::ReturnOutsideCallErr),
+        };
+        let depth = self.frames.borrow().len();
+        // handlers opened by the returning call can no longer be reached
+        self.handlers
+            .borrow_mut()
+            .retain(|handler| handler.frame_depth <= depth);
+        let return_pc = frame.return_pc;
+        self.notify(|observer, stack| observer.on_return(self.pc.get(), return_pc, src, stack));
+        self.jump(return_pc)
+    }
+
+    fn execute_print(&self) -> Result<(), VMError> {
+        if self.stack.borrow().is_empty() {
+            return Err(VMError::StackUnderflowErr(1));
+        }
+        let value = self.pop();
+        writeln!(self.output.borrow_mut(), "{}", value).map_err(VMError::from)
+    }
+
+    fn execute_gload(&self, name: &str) -> Result<(), VMError> {
+        let value = match self.globals.borrow().get(name) {
+            Some(value) => value.clone(),
+            None => return Err(VMError::UndefinedGlobalErr(name.to_string())),
+        };
+        self.push(value)
+    }
+
+    fn execute_gstore(&self, name: &str) -> Result<(), VMError> {
+        if self.stack.borrow().is_empty() {
+            return Err(VMError::StackUnderflowErr(1));
+        }
+        let value = self.pop();
+        self.store_global(name, value)
+    }
+
+    fn store_global(&self, name: &str, value: Vobj) -> Result<(), VMError> {
+        let mut globals = self.globals.borrow_mut();
+        let replaced = globals.get(name).map_or(0, Vobj::heap_size);
+        let heap_bytes = self.heap_bytes.get() - replaced + value.heap_size();
+        if heap_bytes > self.config.max_heap_bytes {
+            return Err(VMError::LimitExceededErr(Limit::HeapBytes(
+                self.config.max_heap_bytes,
+            )));
+        }
+        self.heap_bytes.set(heap_bytes);
+        globals.insert(name.to_string(), value);
+        Ok(())
+    }
+
+    fn execute_throw(&self) -> Result<(), VMError> {
+        if self.stack.borrow().is_empty() {
+            return Err(VMError::StackUnderflowErr(1));
+        }
+        Err(VMError::ThrownErr(self.pop()))
+    }
+
+    fn execute_try_start(&self, br_index: usize) -> Result<(), VMError> {
+        self.check_target(br_index)?;
+        self.handlers.borrow_mut().push(Handler {
+            catch_pc: br_index,
+            stack_len: self.stack.borrow().len(),
+            frame_depth: self.frames.borrow().len(),
+        });
+        Ok(())
+    }
+
+    fn execute_try_end(&self) -> Result<(), VMError> {
+        match self.handlers.borrow_mut().pop() {
+            Some(_) => Ok(()),
+            None => Err(VMError::UnmatchedTryEndErr),
+        }
+    }
+
+    fn execute_native(&self, name: &str) -> Result<(), VMError> {
+        let native = self
+            .natives
+            .get(name)
+            .ok_or_else(|| VMError::UnknownNativeErr(name.to_string()))?;
+        let len = self.stack.borrow().len();
+        if len < native.arity() {
+            return Err(VMError::StackUnderflowErr(native.arity()));
         }
+        let mut args: Vec<Vobj> = (0..native.arity()).map(|_| self.pop()).collect();
+        args.reverse();
+        self.push(native.call(&args)?)
     }
 }
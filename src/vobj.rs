@@ -1,16 +1,271 @@
+use std::cmp::Ordering;
 use std::fmt;
+use std::hash::{Hash, Hasher};
 
-use crate::err::VMError;
+use crate::err::{Position, VMError};
 
-#[derive(Debug, Clone, PartialEq)]
+/// How deeply list literals may nest in assembly and bytecode, so a
+/// malformed input fails to load instead of exhausting the stack.
+pub const MAX_LIST_DEPTH: usize = 64;
+
+/// Values compare and hash by `Vobj::compare`, so `Int(1)` equals
+/// `Double(1.0)`.
+#[derive(Debug, Clone)]
 pub enum Vobj {
     Str(String),
     Double(f64),
     Int(i64),
     Null,
+    Error(Box<ErrorValue>),
+    Bool(bool),
+    List(Vec<Vobj>),
+    Range(IntRange),
+    Iter(Box<IterState>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum VobjKind {
+    Str,
+    Double,
+    Int,
+    Null,
+    Error,
+    Bool,
+    List,
+    Range,
+    Iter,
+}
+
+impl VobjKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            VobjKind::Str => "Str",
+            VobjKind::Double => "Double",
+            VobjKind::Int => "Int",
+            VobjKind::Null => "Null",
+            VobjKind::Error => "Error",
+            VobjKind::Bool => "Bool",
+            VobjKind::List => "List",
+            VobjKind::Range => "Range",
+            VobjKind::Iter => "Iter",
+        }
+    }
+
+    /// The kind called `name`, as `name()` spells it.
+    pub fn from_name(name: &str) -> Option<VobjKind> {
+        match name {
+            "Str" => Some(VobjKind::Str),
+            "Double" => Some(VobjKind::Double),
+            "Int" => Some(VobjKind::Int),
+            "Null" => Some(VobjKind::Null),
+            "Error" => Some(VobjKind::Error),
+            "Bool" => Some(VobjKind::Bool),
+            "List" => Some(VobjKind::List),
+            "Range" => Some(VobjKind::Range),
+            "Iter" => Some(VobjKind::Iter),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for VobjKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Where an iteration over a value stands.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum IterState {
+    /// The ints `next..end`.
+    Count { next: i64, end: i64 },
+    /// The characters of `text` from byte `offset` on, each as a string.
+    Chars { text: String, offset: usize },
+    /// The elements of `values` from `index` on.
+    Items { values: Vec<Vobj>, index: usize },
+    /// The ints of `range` from `index` on.
+    Range { range: IntRange, index: u64 },
+}
+
+impl Iterator for IterState {
+    type Item = Vobj;
+
+    fn next(&mut self) -> Option<Vobj> {
+        match self {
+            IterState::Count { next, end } => {
+                if *next >= *end {
+                    return None;
+                }
+                *next += 1;
+                Some(Vobj::Int(*next - 1))
+            }
+            IterState::Chars { text, offset } => {
+                let c = text.get(*offset..)?.chars().next()?;
+                *offset += c.len_utf8();
+                Some(Vobj::Str(c.to_string()))
+            }
+            IterState::Items { values, index } => {
+                let value = values.get(*index)?.clone();
+                *index += 1;
+                Some(value)
+            }
+            IterState::Range { range, index } => {
+                let value = range.get(*index)?;
+                *index += 1;
+                Some(Vobj::Int(value))
+            }
+        }
+    }
+}
+
+/// The ints from `start` up to, but not including, `stop`, `step` apart. A
+/// negative step counts down. Elements are computed when asked for, and two
+/// ranges are equal when they hold the same ints.
+#[derive(Debug, Clone, Copy)]
+pub struct IntRange {
+    start: i64,
+    stop: i64,
+    step: i64,
+}
+
+impl IntRange {
+    pub fn new(start: i64, stop: i64, step: i64) -> Result<Self, VMError> {
+        if step == 0 {
+            return Err(VMError::IncorrectArgumentErr("range step must not be zero".to_string()));
+        }
+        Ok(Self { start, stop, step })
+    }
+
+    pub fn start(&self) -> i64 {
+        self.start
+    }
+
+    pub fn stop(&self) -> i64 {
+        self.stop
+    }
+
+    pub fn step(&self) -> i64 {
+        self.step
+    }
+
+    pub fn len(&self) -> u64 {
+        let (start, stop, step) = (self.start as i128, self.stop as i128, self.step as i128);
+        let span = if step > 0 { stop - start } else { start - stop };
+        if span <= 0 {
+            0
+        } else {
+            ((span - 1) / step.abs() + 1) as u64
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn get(&self, index: u64) -> Option<i64> {
+        if index >= self.len() {
+            return None;
+        }
+        Some((self.start as i128 + index as i128 * self.step as i128) as i64)
+    }
+
+    /// The index of `value`, if the range holds it.
+    pub fn position(&self, value: i64) -> Option<u64> {
+        let offset = value as i128 - self.start as i128;
+        let step = self.step as i128;
+        if offset % step != 0 || offset / step < 0 {
+            return None;
+        }
+        let index = (offset / step) as u64;
+        (index < self.len()).then_some(index)
+    }
+
+    // Ranges holding the same ints have the same key.
+    fn key(&self) -> (u64, i64, i64) {
+        let len = self.len();
+        let start = if len > 0 { self.start } else { 0 };
+        let step = if len > 1 { self.step } else { 0 };
+        (len, start, step)
+    }
+}
+
+impl PartialEq for IntRange {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for IntRange {}
+
+impl PartialOrd for IntRange {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for IntRange {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key().cmp(&other.key())
+    }
+}
+
+impl Hash for IntRange {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.key().hash(state);
+    }
+}
+
+impl fmt::Display for IntRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.step {
+            1 => write!(f, "range({}, {})", self.start, self.stop),
+            step => write!(f, "range({}, {}, {})", self.start, self.stop, step),
+        }
+    }
+}
+
+/// A caught runtime error.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ErrorValue {
+    pub kind: String,
+    pub message: String,
 }
 
 impl Vobj {
+    pub fn kind(&self) -> VobjKind {
+        match self {
+            Vobj::Str(_) => VobjKind::Str,
+            Vobj::Double(_) => VobjKind::Double,
+            Vobj::Int(_) => VobjKind::Int,
+            Vobj::Null => VobjKind::Null,
+            Vobj::Error(_) => VobjKind::Error,
+            Vobj::Bool(_) => VobjKind::Bool,
+            Vobj::List(_) => VobjKind::List,
+            Vobj::Range(_) => VobjKind::Range,
+            Vobj::Iter(_) => VobjKind::Iter,
+        }
+    }
+
+    /// The name of this value's kind, e.g. `"Int"`.
+    pub fn type_of(&self) -> &'static str {
+        self.kind().name()
+    }
+
+    /// Bytes this value holds outside of the operand stack slot itself.
+    pub fn heap_size(&self) -> usize {
+        match self {
+            Vobj::Str(value) => value.len(),
+            Vobj::Error(error) => error.kind.len() + error.message.len(),
+            Vobj::List(values) => list_heap_size(values),
+            Vobj::Iter(state) => match &**state {
+                IterState::Count { .. } | IterState::Range { .. } => 0,
+                IterState::Chars { text, .. } => text.len(),
+                IterState::Items { values, .. } => list_heap_size(values),
+            },
+            _ => 0,
+        }
+    }
+
     pub fn add(oprand1: &Vobj, oprand2: &Vobj) -> Result<Vobj, VMError> {
         match *oprand1 {
             Vobj::Double(value1) => {
@@ -19,9 +274,7 @@ impl Vobj {
                 } else if let Vobj::Int(value2) = *oprand2 {
                     Ok(Vobj::Double(value1 + value2 as f64))
                 } else {
-                    Err(VMError::IncorrectArgumentErr(String::from(
-                        "Expected numerical type",
-                    )))
+                    Err(numeric_type_error("Add", Position::Right, oprand2))
                 }
             }
 
@@ -29,16 +282,12 @@ impl Vobj {
                 if let Vobj::Double(value2) = *oprand2 {
                     Ok(Vobj::Double(value1 as f64 + value2))
                 } else if let Vobj::Int(value2) = *oprand2 {
-                    Ok(Vobj::Int(value1 + value2))
+                    value1.checked_add(value2).map(Vobj::Int).ok_or(VMError::OverflowErr("Add"))
                 } else {
-                    Err(VMError::IncorrectArgumentErr(String::from(
-                        "Expected numerical type",
-                    )))
+                    Err(numeric_type_error("Add", Position::Right, oprand2))
                 }
             }
-            _ => Err(VMError::IncorrectArgumentErr(String::from(
-                "Expected numerical type",
-            ))),
+            _ => Err(numeric_type_error("Add", Position::Left, oprand1)),
         }
     }
 
@@ -50,9 +299,7 @@ impl Vobj {
                 } else if let Vobj::Int(value2) = *oprand2 {
                     Ok(Vobj::Double(value1 - value2 as f64))
                 } else {
-                    Err(VMError::IncorrectArgumentErr(String::from(
-                        "Expected numerical type",
-                    )))
+                    Err(numeric_type_error("Sub", Position::Right, oprand2))
                 }
             }
 
@@ -60,16 +307,12 @@ impl Vobj {
                 if let Vobj::Double(value2) = *oprand2 {
                     Ok(Vobj::Double(value1 as f64 - value2))
                 } else if let Vobj::Int(value2) = *oprand2 {
-                    Ok(Vobj::Int(value1 - value2))
+                    value1.checked_sub(value2).map(Vobj::Int).ok_or(VMError::OverflowErr("Sub"))
                 } else {
-                    Err(VMError::IncorrectArgumentErr(String::from(
-                        "Expected numerical type",
-                    )))
+                    Err(numeric_type_error("Sub", Position::Right, oprand2))
                 }
             }
-            _ => Err(VMError::IncorrectArgumentErr(String::from(
-                "Expected numerical type",
-            ))),
+            _ => Err(numeric_type_error("Sub", Position::Left, oprand1)),
         }
     }
 
@@ -81,9 +324,7 @@ impl Vobj {
                 } else if let Vobj::Int(value2) = *oprand2 {
                     Ok(Vobj::Double(value1 * value2 as f64))
                 } else {
-                    Err(VMError::IncorrectArgumentErr(String::from(
-                        "Expected numerical type",
-                    )))
+                    Err(numeric_type_error("Mul", Position::Right, oprand2))
                 }
             }
 
@@ -91,16 +332,12 @@ impl Vobj {
                 if let Vobj::Double(value2) = *oprand2 {
                     Ok(Vobj::Double(value1 as f64 * value2))
                 } else if let Vobj::Int(value2) = *oprand2 {
-                    Ok(Vobj::Int(value1 * value2))
+                    value1.checked_mul(value2).map(Vobj::Int).ok_or(VMError::OverflowErr("Mul"))
                 } else {
-                    Err(VMError::IncorrectArgumentErr(String::from(
-                        "Expected numerical type",
-                    )))
+                    Err(numeric_type_error("Mul", Position::Right, oprand2))
                 }
             }
-            _ => Err(VMError::IncorrectArgumentErr(String::from(
-                "Expected numerical type",
-            ))),
+            _ => Err(numeric_type_error("Mul", Position::Left, oprand1)),
         }
     }
 
@@ -120,9 +357,7 @@ impl Vobj {
                         Ok(Vobj::Double(value1 / value2 as f64))
                     }
                 } else {
-                    Err(VMError::IncorrectArgumentErr(String::from(
-                        "Expected numerical type",
-                    )))
+                    Err(numeric_type_error("Div", Position::Right, oprand2))
                 }
             }
 
@@ -137,146 +372,599 @@ impl Vobj {
                     if value2 == 0 {
                         Err(VMError::DivisionByZeroErr)
                     } else {
-                        Ok(Vobj::Int(value1 / value2))
+                        value1.checked_div(value2).map(Vobj::Int).ok_or(VMError::OverflowErr("Div"))
                     }
                 } else {
-                    Err(VMError::IncorrectArgumentErr(String::from(
-                        "Expected numerical type",
-                    )))
+                    Err(numeric_type_error("Div", Position::Right, oprand2))
                 }
             }
-            _ => Err(VMError::IncorrectArgumentErr(String::from(
-                "Expected numerical type",
-            ))),
+            _ => Err(numeric_type_error("Div", Position::Left, oprand1)),
+        }
+    }
+
+    /// The total order every comparison in the vm uses. Kinds sort as
+    /// null, bool, number, string, list, range, iterator, error. Ints and
+    /// doubles compare by exact numeric value and NaN sorts above every other
+    /// number, equal to itself. Strings compare bytewise, lists element by
+    /// element, ranges by the ints they hold, iterators by their state and
+    /// errors by kind and then message.
+    pub fn compare(&self, other: &Vobj) -> Ordering {
+        match (self, other) {
+            (Vobj::Int(value1), Vobj::Int(value2)) => value1.cmp(value2),
+            (Vobj::Int(value1), Vobj::Double(value2)) => compare_int_double(*value1, *value2),
+            (Vobj::Double(value1), Vobj::Int(value2)) => compare_int_double(*value2, *value1).reverse(),
+            (Vobj::Double(value1), Vobj::Double(value2)) => compare_doubles(*value1, *value2),
+            (Vobj::Str(value1), Vobj::Str(value2)) => value1.cmp(value2),
+            (Vobj::Bool(value1), Vobj::Bool(value2)) => value1.cmp(value2),
+            (Vobj::Error(value1), Vobj::Error(value2)) => value1.cmp(value2),
+            (Vobj::List(value1), Vobj::List(value2)) => value1.cmp(value2),
+            (Vobj::Range(value1), Vobj::Range(value2)) => value1.cmp(value2),
+            (Vobj::Iter(value1), Vobj::Iter(value2)) => value1.cmp(value2),
+            _ => self.rank().cmp(&other.rank()),
+        }
+    }
+
+    // Where this value's kind sorts among the others.
+    fn rank(&self) -> u8 {
+        match self {
+            Vobj::Null => 0,
+            Vobj::Bool(_) => 1,
+            Vobj::Int(_) | Vobj::Double(_) => 2,
+            Vobj::Str(_) => 3,
+            Vobj::List(_) => 4,
+            Vobj::Range(_) => 5,
+            Vobj::Iter(_) => 6,
+            Vobj::Error(_) => 7,
         }
     }
 
     pub fn greater_than(oprand1: &Vobj, oprand2: &Vobj) -> Result<bool, VMError> {
-        match *oprand1 {
-            Vobj::Double(value1) => {
-                if let Vobj::Double(value2) = *oprand2 {
-                    Ok(value1>value2)
-                } else if let Vobj::Int(value2) = *oprand2 {
-                    Ok(value1>value2 as f64)
+        Ok(oprand1.compare(oprand2).is_gt())
+    }
+
+    pub fn greater_eq(oprand1: &Vobj, oprand2: &Vobj) -> Result<bool, VMError> {
+        Ok(oprand1.compare(oprand2).is_ge())
+    }
+
+    pub fn less_than(oprand1: &Vobj, oprand2: &Vobj) -> Result<bool, VMError> {
+        Ok(oprand1.compare(oprand2).is_lt())
+    }
+
+    pub fn less_eq(oprand1: &Vobj, oprand2: &Vobj) -> Result<bool, VMError> {
+        Ok(oprand1.compare(oprand2).is_le())
+    }
+
+    /// Remainder of truncating division; the result has the sign of the dividend.
+    pub fn rem(oprand1: &Vobj, oprand2: &Vobj) -> Result<Vobj, VMError> {
+        match numeric_pair("Rem", oprand1, oprand2)? {
+            Numbers::Int(_, 0) => Err(VMError::DivisionByZeroErr),
+            Numbers::Int(value1, value2) => value1
+                .checked_rem(value2)
+                .map(Vobj::Int)
+                .ok_or(VMError::OverflowErr("Rem")),
+            Numbers::Double(_, 0.0) => Err(VMError::DivisionByZeroErr),
+            Numbers::Double(value1, value2) => Ok(Vobj::Double(value1 % value2)),
+        }
+    }
+
+    /// Remainder of flooring division; the result has the sign of the divisor.
+    pub fn modulo(oprand1: &Vobj, oprand2: &Vobj) -> Result<Vobj, VMError> {
+        match numeric_pair("Mod", oprand1, oprand2)? {
+            Numbers::Int(_, 0) => Err(VMError::DivisionByZeroErr),
+            Numbers::Int(value1, value2) => {
+                // i64::MIN % -1 is 0 mathematically but overflows in hardware
+                let rem = value1.checked_rem(value2).unwrap_or(0);
+                if rem != 0 && (rem < 0) != (value2 < 0) {
+                    Ok(Vobj::Int(rem + value2))
                 } else {
-                    Err(VMError::IncorrectArgumentErr(String::from(
-                        "Expected numerical type",
-                    )))
+                    Ok(Vobj::Int(rem))
                 }
             }
-
-            Vobj::Int(value1) => {
-                if let Vobj::Double(value2) = *oprand2 {
-                    Ok(value1 as f64>value2)
-                } else if let Vobj::Int(value2) = *oprand2 {
-                    Ok(value1 >value2)
+            Numbers::Double(_, 0.0) => Err(VMError::DivisionByZeroErr),
+            Numbers::Double(value1, value2) => {
+                let rem = value1 % value2;
+                if rem != 0.0 && (rem < 0.0) != (value2 < 0.0) {
+                    Ok(Vobj::Double(rem + value2))
                 } else {
-                    Err(VMError::IncorrectArgumentErr(String::from(
-                        "Expected numerical type",
-                    )))
+                    Ok(Vobj::Double(rem))
                 }
             }
-            _ => Err(VMError::IncorrectArgumentErr(String::from(
-                "Expected numerical type",
-            ))),
         }
     }
 
-    pub fn greater_eq(oprand1: &Vobj, oprand2: &Vobj) -> Result<bool, VMError> {
-        match *oprand1 {
-            Vobj::Double(value1) => {
-                if let Vobj::Double(value2) = *oprand2 {
-                    Ok(value1>=value2)
-                } else if let Vobj::Int(value2) = *oprand2 {
-                    Ok(value1>=value2 as f64)
+    /// Division rounded towards negative infinity.
+    pub fn floor_div(oprand1: &Vobj, oprand2: &Vobj) -> Result<Vobj, VMError> {
+        match numeric_pair("FloorDiv", oprand1, oprand2)? {
+            Numbers::Int(_, 0) => Err(VMError::DivisionByZeroErr),
+            Numbers::Int(value1, value2) => {
+                let quotient = value1
+                    .checked_div(value2)
+                    .ok_or(VMError::OverflowErr("FloorDiv"))?;
+                if value1 % value2 != 0 && (value1 < 0) != (value2 < 0) {
+                    Ok(Vobj::Int(quotient - 1))
                 } else {
-                    Err(VMError::IncorrectArgumentErr(String::from(
-                        "Expected numerical type",
-                    )))
+                    Ok(Vobj::Int(quotient))
                 }
             }
+            Numbers::Double(_, 0.0) => Err(VMError::DivisionByZeroErr),
+            Numbers::Double(value1, value2) => Ok(Vobj::Double((value1 / value2).floor())),
+        }
+    }
 
-            Vobj::Int(value1) => {
-                if let Vobj::Double(value2) = *oprand2 {
-                    Ok(value1 as f64>=value2)
-                } else if let Vobj::Int(value2) = *oprand2 {
-                    Ok(value1 >=value2)
-                } else {
-                    Err(VMError::IncorrectArgumentErr(String::from(
-                        "Expected numerical type",
-                    )))
-                }
-            }
-            _ => Err(VMError::IncorrectArgumentErr(String::from(
-                "Expected numerical type",
-            ))),
+    /// Division that always produces a `Double`.
+    pub fn true_div(oprand1: &Vobj, oprand2: &Vobj) -> Result<Vobj, VMError> {
+        let (value1, value2) = match numeric_pair("TrueDiv", oprand1, oprand2)? {
+            Numbers::Int(value1, value2) => (value1 as f64, value2 as f64),
+            Numbers::Double(value1, value2) => (value1, value2),
+        };
+        if value2 == 0.0 {
+            Err(VMError::DivisionByZeroErr)
+        } else {
+            Ok(Vobj::Double(value1 / value2))
         }
     }
 
-    pub fn less_than(oprand1: &Vobj, oprand2: &Vobj) -> Result<bool, VMError> {
-        match *oprand1 {
-            Vobj::Double(value1) => {
-                if let Vobj::Double(value2) = *oprand2 {
-                    Ok(value1<value2)
-                } else if let Vobj::Int(value2) = *oprand2 {
-                    Ok(value1<value2 as f64)
-                } else {
-                    Err(VMError::IncorrectArgumentErr(String::from(
-                        "Expected numerical type",
-                    )))
-                }
+    /// `Int` to a non-negative `Int` power stays an `Int`; anything else is a
+    /// `Double`.
+    pub fn pow(oprand1: &Vobj, oprand2: &Vobj) -> Result<Vobj, VMError> {
+        match numeric_pair("Pow", oprand1, oprand2)? {
+            Numbers::Int(0, exponent) if exponent < 0 => Err(VMError::DivisionByZeroErr),
+            Numbers::Int(base, exponent) if exponent < 0 => {
+                Ok(Vobj::Double((base as f64).powf(exponent as f64)))
+            }
+            // -1, 0 and 1 stay in range however large the exponent gets
+            Numbers::Int(base @ -1..=1, exponent) if exponent > 0 => {
+                Ok(Vobj::Int(if base == -1 && exponent % 2 == 0 { 1 } else { base }))
+            }
+            Numbers::Int(base, exponent) => u32::try_from(exponent)
+                .ok()
+                .and_then(|exponent| base.checked_pow(exponent))
+                .map(Vobj::Int)
+                .ok_or(VMError::OverflowErr("Pow")),
+            Numbers::Double(base, exponent) if base == 0.0 && exponent < 0.0 => {
+                Err(VMError::DivisionByZeroErr)
             }
+            Numbers::Double(base, exponent) => Ok(Vobj::Double(base.powf(exponent))),
+        }
+    }
 
-            Vobj::Int(value1) => {
-                if let Vobj::Double(value2) = *oprand2 {
-                    Ok((value1 as f64) < value2)
-                } else if let Vobj::Int(value2) = *oprand2 {
-                    Ok(value1 <value2)
-                } else {
-                    Err(VMError::IncorrectArgumentErr(String::from(
-                        "Expected numerical type",
-                    )))
-                }
+    pub fn neg(oprand: &Vobj) -> Result<Vobj, VMError> {
+        match *oprand {
+            Vobj::Int(value) => value
+                .checked_neg()
+                .map(Vobj::Int)
+                .ok_or(VMError::OverflowErr("Neg")),
+            Vobj::Double(value) => Ok(Vobj::Double(-value)),
+            _ => Err(numeric_type_error("Neg", Position::Only, oprand)),
+        }
+    }
+
+    pub fn abs(oprand: &Vobj) -> Result<Vobj, VMError> {
+        match *oprand {
+            Vobj::Int(value) => value
+                .checked_abs()
+                .map(Vobj::Int)
+                .ok_or(VMError::OverflowErr("Abs")),
+            Vobj::Double(value) => Ok(Vobj::Double(value.abs())),
+            _ => Err(numeric_type_error("Abs", Position::Only, oprand)),
+        }
+    }
+
+    pub fn bit_and(oprand1: &Vobj, oprand2: &Vobj) -> Result<Vobj, VMError> {
+        let (value1, value2) = int_pair("And", oprand1, oprand2)?;
+        Ok(Vobj::Int(value1 & value2))
+    }
+
+    pub fn bit_or(oprand1: &Vobj, oprand2: &Vobj) -> Result<Vobj, VMError> {
+        let (value1, value2) = int_pair("Or", oprand1, oprand2)?;
+        Ok(Vobj::Int(value1 | value2))
+    }
+
+    pub fn bit_xor(oprand1: &Vobj, oprand2: &Vobj) -> Result<Vobj, VMError> {
+        let (value1, value2) = int_pair("Xor", oprand1, oprand2)?;
+        Ok(Vobj::Int(value1 ^ value2))
+    }
+
+    pub fn bit_not(oprand: &Vobj) -> Result<Vobj, VMError> {
+        match *oprand {
+            Vobj::Int(value) => Ok(Vobj::Int(!value)),
+            _ => Err(int_type_error("Not", Position::Only, oprand)),
+        }
+    }
+
+    /// Shifts left, discarding the bits shifted out.
+    pub fn shl(oprand1: &Vobj, oprand2: &Vobj) -> Result<Vobj, VMError> {
+        let (value, amount) = int_pair("Shl", oprand1, oprand2)?;
+        Ok(Vobj::Int(value << shift_amount(amount)?))
+    }
+
+    /// Shifts right, copying the sign bit.
+    pub fn sar(oprand1: &Vobj, oprand2: &Vobj) -> Result<Vobj, VMError> {
+        let (value, amount) = int_pair("Sar", oprand1, oprand2)?;
+        Ok(Vobj::Int(value >> shift_amount(amount)?))
+    }
+
+    /// Shifts right, filling with zeros.
+    pub fn shr(oprand1: &Vobj, oprand2: &Vobj) -> Result<Vobj, VMError> {
+        let (value, amount) = int_pair("Shr", oprand1, oprand2)?;
+        Ok(Vobj::Int(((value as u64) >> shift_amount(amount)?) as i64))
+    }
+
+    pub fn popcount(oprand: &Vobj) -> Result<Vobj, VMError> {
+        match *oprand {
+            Vobj::Int(value) => Ok(Vobj::Int(value.count_ones() as i64)),
+            _ => Err(int_type_error("Popcount", Position::Only, oprand)),
+        }
+    }
+
+    /// An iterator over the characters of a string, the elements of a list
+    /// or, for an int `n`, the ints `0..n`. An iterator is returned as is.
+    pub fn iter(oprand: &Vobj) -> Result<Vobj, VMError> {
+        let state = match oprand {
+            Vobj::Int(end) => IterState::Count { next: 0, end: *end },
+            Vobj::Str(text) => IterState::Chars { text: text.clone(), offset: 0 },
+            Vobj::List(values) => IterState::Items { values: values.clone(), index: 0 },
+            Vobj::Range(range) => IterState::Range { range: *range, index: 0 },
+            Vobj::Iter(_) => return Ok(oprand.clone()),
+            _ => {
+                return Err(VMError::TypeErr {
+                    operation: "IterNew",
+                    position: Position::Only,
+                    expected: vec![VobjKind::Int, VobjKind::Str, VobjKind::List, VobjKind::Range, VobjKind::Iter],
+                    actual: oprand.kind(),
+                })
             }
-            _ => Err(VMError::IncorrectArgumentErr(String::from(
-                "Expected numerical type",
-            ))),
+        };
+        Ok(Vobj::Iter(Box::new(state)))
+    }
+
+    /// The ints from `start` up to `stop`, counting by one.
+    pub fn range(oprand1: &Vobj, oprand2: &Vobj) -> Result<Vobj, VMError> {
+        let (start, stop) = int_pair("Range", oprand1, oprand2)?;
+        Ok(Vobj::Range(IntRange::new(start, stop, 1)?))
+    }
+
+    pub fn range_step(start: &Vobj, stop: &Vobj, step: &Vobj) -> Result<Vobj, VMError> {
+        let mut bounds = [0; 3];
+        for (index, oprand) in [start, stop, step].into_iter().enumerate() {
+            bounds[index] = match *oprand {
+                Vobj::Int(value) => value,
+                _ => return Err(int_type_error("RangeStep", Position::Argument(index + 1), oprand)),
+            };
         }
+        Ok(Vobj::Range(IntRange::new(bounds[0], bounds[1], bounds[2])?))
     }
 
-    pub fn less_eq(oprand1: &Vobj, oprand2: &Vobj) -> Result<bool, VMError> {
-        match *oprand1 {
-            Vobj::Double(value1) => {
-                if let Vobj::Double(value2) = *oprand2 {
-                    Ok(value1<=value2)
-                } else if let Vobj::Int(value2) = *oprand2 {
-                    Ok(value1<=value2 as f64)
-                } else {
-                    Err(VMError::IncorrectArgumentErr(String::from(
-                        "Expected numerical type",
-                    )))
-                }
+    /// The number of elements of a list or range, or of characters of a
+    /// string.
+    pub fn len(oprand: &Vobj) -> Result<Vobj, VMError> {
+        match oprand {
+            Vobj::List(values) => Ok(Vobj::Int(values.len() as i64)),
+            Vobj::Str(value) => Ok(Vobj::Int(value.chars().count() as i64)),
+            Vobj::Range(range) => i64::try_from(range.len()).map(Vobj::Int).map_err(|_| VMError::OverflowErr("Len")),
+            _ => Err(VMError::TypeErr {
+                operation: "Len",
+                position: Position::Only,
+                expected: vec![VobjKind::List, VobjKind::Str, VobjKind::Range],
+                actual: oprand.kind(),
+            }),
+        }
+    }
+
+    pub fn get(oprand1: &Vobj, oprand2: &Vobj) -> Result<Vobj, VMError> {
+        let index = match (oprand1, oprand2) {
+            (Vobj::List(_) | Vobj::Range(_), Vobj::Int(index)) => *index,
+            (Vobj::List(_) | Vobj::Range(_), _) => return Err(int_type_error("Get", Position::Right, oprand2)),
+            _ => return Err(sequence_type_error("Get", oprand1)),
+        };
+        if let Vobj::Range(range) = oprand1 {
+            return u64::try_from(index)
+                .ok()
+                .and_then(|index| range.get(index))
+                .map(Vobj::Int)
+                .ok_or(VMError::IndexOutOfRangeErr { index, len: range.len() as usize });
+        }
+        let values = list("Get", Position::Left, oprand1)?;
+        usize::try_from(index)
+            .ok()
+            .and_then(|index| values.get(index))
+            .cloned()
+            .ok_or(VMError::IndexOutOfRangeErr { index, len: values.len() })
+    }
+
+    /// Sorts by `compare`, keeping equal elements in their original order.
+    pub fn sort(oprand: &Vobj) -> Result<Vobj, VMError> {
+        let mut values = list("Sort", Position::Only, oprand)?.to_vec();
+        values.sort();
+        Ok(Vobj::List(values))
+    }
+
+    pub fn reverse(oprand: &Vobj) -> Result<Vobj, VMError> {
+        let mut values = list("Reverse", Position::Only, oprand)?.to_vec();
+        values.reverse();
+        Ok(Vobj::List(values))
+    }
+
+    pub fn contains(oprand1: &Vobj, oprand2: &Vobj) -> Result<Vobj, VMError> {
+        match oprand1 {
+            Vobj::List(values) => Ok(Vobj::Bool(values.contains(oprand2))),
+            Vobj::Range(range) => Ok(Vobj::Bool(range_position(range, oprand2).is_some())),
+            _ => Err(sequence_type_error("Contains", oprand1)),
+        }
+    }
+
+    /// The index of the first element equal to the value, or null.
+    pub fn index_of(oprand1: &Vobj, oprand2: &Vobj) -> Result<Vobj, VMError> {
+        let values = match oprand1 {
+            Vobj::List(values) => values,
+            Vobj::Range(range) => {
+                return Ok(range_position(range, oprand2).map_or(Vobj::Null, |index| Vobj::Int(index as i64)))
             }
+            _ => return Err(sequence_type_error("IndexOf", oprand1)),
+        };
+        Ok(values
+            .iter()
+            .position(|value| value == oprand2)
+            .map_or(Vobj::Null, |index| Vobj::Int(index as i64)))
+    }
 
-            Vobj::Int(value1) => {
-                if let Vobj::Double(value2) = *oprand2 {
-                    Ok(value1 as f64<=value2)
-                } else if let Vobj::Int(value2) = *oprand2 {
-                    Ok(value1 <=value2)
-                } else {
-                    Err(VMError::IncorrectArgumentErr(String::from(
-                        "Expected numerical type",
-                    )))
-                }
+    /// The smallest element, or null for an empty list.
+    pub fn minimum(oprand: &Vobj) -> Result<Vobj, VMError> {
+        let values = list("Min", Position::Only, oprand)?;
+        Ok(values.iter().min().cloned().unwrap_or_default())
+    }
+
+    /// The largest element, or null for an empty list.
+    pub fn maximum(oprand: &Vobj) -> Result<Vobj, VMError> {
+        let values = list("Max", Position::Only, oprand)?;
+        Ok(values.iter().max().cloned().unwrap_or_default())
+    }
+
+    /// Adds the elements like `add` does, starting from `Int(0)`.
+    pub fn sum(oprand: &Vobj) -> Result<Vobj, VMError> {
+        let values = list("Sum", Position::Only, oprand)?;
+        values.iter().enumerate().try_fold(Vobj::Int(0), |total, (index, value)| match value {
+            Vobj::Int(_) | Vobj::Double(_) => Vobj::add(&total, value).map_err(|_| VMError::OverflowErr("Sum")),
+            _ => Err(numeric_type_error("Sum", Position::Element(index), value)),
+        })
+    }
+
+    /// The elements as `Print` would write them, separated by a string.
+    pub fn join(oprand1: &Vobj, oprand2: &Vobj) -> Result<Vobj, VMError> {
+        let values = list("Join", Position::Left, oprand1)?;
+        let separator = match oprand2 {
+            Vobj::Str(separator) => separator,
+            _ => {
+                return Err(VMError::TypeErr {
+                    operation: "Join",
+                    position: Position::Right,
+                    expected: vec![VobjKind::Str],
+                    actual: oprand2.kind(),
+                })
+            }
+        };
+        let values: Vec<String> = values.iter().map(Vobj::to_string).collect();
+        Ok(Vobj::Str(values.join(separator)))
+    }
+
+    /// Doubles are truncated toward zero; strings are parsed like
+    /// `parse_num` first; `true` is 1 and `false` 0.
+    pub fn to_int(oprand: &Vobj) -> Result<Vobj, VMError> {
+        match oprand {
+            Vobj::Int(value) => Ok(Vobj::Int(*value)),
+            Vobj::Double(value) => double_to_int(*value).ok_or_else(|| conversion_error(oprand, "Int")),
+            Vobj::Str(_) => match Vobj::parse_num(oprand) {
+                Ok(Vobj::Double(value)) => double_to_int(value).ok_or_else(|| conversion_error(oprand, "Int")),
+                Ok(number) => Ok(number),
+                Err(_) => Err(conversion_error(oprand, "Int")),
+            },
+            Vobj::Bool(value) => Ok(Vobj::Int(*value as i64)),
+            _ => Err(conversion_type_error("ToInt", oprand)),
+        }
+    }
+
+    pub fn to_double(oprand: &Vobj) -> Result<Vobj, VMError> {
+        match oprand {
+            Vobj::Int(value) => Ok(Vobj::Double(*value as f64)),
+            Vobj::Double(value) => Ok(Vobj::Double(*value)),
+            Vobj::Str(value) => value
+                .trim()
+                .parse::<f64>()
+                .map(Vobj::Double)
+                .map_err(|_| conversion_error(oprand, "Double")),
+            Vobj::Bool(value) => Ok(Vobj::Double(*value as i64 as f64)),
+            _ => Err(conversion_type_error("ToDouble", oprand)),
+        }
+    }
+
+    /// The text `Print` would write.
+    pub fn to_str(oprand: &Vobj) -> Result<Vobj, VMError> {
+        match oprand {
+            Vobj::Str(_) => Ok(oprand.clone()),
+            _ => Ok(Vobj::Str(oprand.to_string())),
+        }
+    }
+
+    /// Numbers are true when non-zero, null is false and strings must read
+    /// `true` or `false`, ignoring case.
+    pub fn to_bool(oprand: &Vobj) -> Result<Vobj, VMError> {
+        match oprand {
+            Vobj::Bool(value) => Ok(Vobj::Bool(*value)),
+            Vobj::Int(value) => Ok(Vobj::Bool(*value != 0)),
+            Vobj::Double(value) => Ok(Vobj::Bool(*value != 0.0)),
+            Vobj::Null => Ok(Vobj::Bool(false)),
+            Vobj::Str(value) => match value.trim().to_ascii_lowercase().as_str() {
+                "true" => Ok(Vobj::Bool(true)),
+                "false" => Ok(Vobj::Bool(false)),
+                _ => Err(conversion_error(oprand, "Bool")),
+            },
+            _ => Err(conversion_type_error("ToBool", oprand)),
+        }
+    }
+
+    /// An `Int` when the string is a whole number that fits, otherwise a
+    /// `Double`. Surrounding whitespace is ignored.
+    pub fn parse_num(oprand: &Vobj) -> Result<Vobj, VMError> {
+        let text = match oprand {
+            Vobj::Str(value) => value.trim(),
+            _ => {
+                return Err(VMError::TypeErr {
+                    operation: "ParseNum",
+                    position: Position::Only,
+                    expected: vec![VobjKind::Str],
+                    actual: oprand.kind(),
+                })
             }
-            _ => Err(VMError::IncorrectArgumentErr(String::from(
-                "Expected numerical type",
-            ))),
+        };
+        if let Ok(value) = text.parse::<i64>() {
+            return Ok(Vobj::Int(value));
         }
+        text.parse::<f64>()
+            .map(Vobj::Double)
+            .map_err(|_| conversion_error(oprand, "a number"))
+    }
+}
+
+fn compare_doubles(value1: f64, value2: f64) -> Ordering {
+    match (value1.is_nan(), value2.is_nan()) {
+        (true, true) => Ordering::Equal,
+        (true, false) => Ordering::Greater,
+        (false, true) => Ordering::Less,
+        (false, false) => value1.partial_cmp(&value2).unwrap_or(Ordering::Equal),
+    }
+}
+
+// Exact, where `value1 as f64` would round large ints.
+fn compare_int_double(value1: i64, value2: f64) -> Ordering {
+    const LIMIT: f64 = 9_223_372_036_854_775_808.0; // 2^63
+    if value2.is_nan() || value2 >= LIMIT {
+        return Ordering::Less;
+    }
+    if value2 < -LIMIT {
+        return Ordering::Greater;
+    }
+    let whole = value2.trunc();
+    value1
+        .cmp(&(whole as i64))
+        .then_with(|| 0.0.partial_cmp(&(value2 - whole)).unwrap_or(Ordering::Equal))
+}
+
+// The Int a double equals, if any.
+fn exact_int(value: f64) -> Option<i64> {
+    match compare_int_double(value as i64, value) {
+        Ordering::Equal => Some(value as i64),
+        _ => None,
+    }
+}
+
+// Two numeric operands, promoted to `Double` unless both are `Int`.
+enum Numbers {
+    Int(i64, i64),
+    Double(f64, f64),
+}
+
+fn numeric_pair(operation: &'static str, oprand1: &Vobj, oprand2: &Vobj) -> Result<Numbers, VMError> {
+    match (oprand1, oprand2) {
+        (Vobj::Int(value1), Vobj::Int(value2)) => Ok(Numbers::Int(*value1, *value2)),
+        (Vobj::Int(value1), Vobj::Double(value2)) => Ok(Numbers::Double(*value1 as f64, *value2)),
+        (Vobj::Double(value1), Vobj::Int(value2)) => Ok(Numbers::Double(*value1, *value2 as f64)),
+        (Vobj::Double(value1), Vobj::Double(value2)) => Ok(Numbers::Double(*value1, *value2)),
+        (Vobj::Int(_) | Vobj::Double(_), _) => Err(numeric_type_error(operation, Position::Right, oprand2)),
+        _ => Err(numeric_type_error(operation, Position::Left, oprand1)),
+    }
+}
+
+fn int_pair(operation: &'static str, oprand1: &Vobj, oprand2: &Vobj) -> Result<(i64, i64), VMError> {
+    match (oprand1, oprand2) {
+        (Vobj::Int(value1), Vobj::Int(value2)) => Ok((*value1, *value2)),
+        (Vobj::Int(_), _) => Err(int_type_error(operation, Position::Right, oprand2)),
+        _ => Err(int_type_error(operation, Position::Left, oprand1)),
+    }
+}
+
+fn shift_amount(amount: i64) -> Result<u32, VMError> {
+    match u32::try_from(amount) {
+        Ok(amount) if amount < i64::BITS => Ok(amount),
+        _ => Err(VMError::InvalidShiftErr(amount)),
+    }
+}
+
+// Truncates toward zero, or None when the result is not an `i64`.
+fn double_to_int(value: f64) -> Option<Vobj> {
+    let value = value.trunc();
+    if value >= i64::MIN as f64 && value < i64::MAX as f64 {
+        Some(Vobj::Int(value as i64))
+    } else {
+        None
+    }
+}
+
+fn conversion_error(value: &Vobj, target: &'static str) -> VMError {
+    VMError::ConversionErr { value: value.clone(), target }
+}
+
+fn conversion_type_error(operation: &'static str, actual: &Vobj) -> VMError {
+    VMError::TypeErr {
+        operation,
+        position: Position::Only,
+        expected: vec![VobjKind::Int, VobjKind::Double, VobjKind::Str, VobjKind::Bool],
+        actual: actual.kind(),
+    }
+}
+
+fn list_heap_size(values: &[Vobj]) -> usize {
+    std::mem::size_of_val(values) + values.iter().map(Vobj::heap_size).sum::<usize>()
+}
+
+// Where a range holds a number equal to `value`, if anywhere.
+fn range_position(range: &IntRange, value: &Vobj) -> Option<u64> {
+    match *value {
+        Vobj::Int(value) => range.position(value),
+        Vobj::Double(value) => range.position(exact_int(value)?),
+        _ => None,
+    }
+}
+
+fn sequence_type_error(operation: &'static str, actual: &Vobj) -> VMError {
+    VMError::TypeErr {
+        operation,
+        position: Position::Left,
+        expected: vec![VobjKind::List, VobjKind::Range],
+        actual: actual.kind(),
+    }
+}
+
+fn list<'a>(operation: &'static str, position: Position, oprand: &'a Vobj) -> Result<&'a [Vobj], VMError> {
+    match oprand {
+        Vobj::List(values) => Ok(values),
+        _ => Err(VMError::TypeErr {
+            operation,
+            position,
+            expected: vec![VobjKind::List],
+            actual: oprand.kind(),
+        }),
     }
+}
+
+fn int_type_error(operation: &'static str, position: Position, actual: &Vobj) -> VMError {
+    VMError::TypeErr {
+        operation,
+        position,
+        expected: vec![VobjKind::Int],
+        actual: actual.kind(),
+    }
+}
 
+fn numeric_type_error(operation: &'static str, position: Position, actual: &Vobj) -> VMError {
+    VMError::TypeErr {
+        operation,
+        position,
+        expected: vec![VobjKind::Int, VobjKind::Double],
+        actual: actual.kind(),
+    }
 }
 
+#[allow(clippy::derivable_impls)]
 impl Default for Vobj {
     fn default() -> Self {
         Vobj::Null
@@ -295,12 +983,72 @@ impl From<i64> for Vobj {
     } 
 }
 
+impl From<bool> for Vobj {
+    fn from(value: bool) -> Self {
+        Vobj::Bool(value)
+    }
+}
+
 impl From<&str> for Vobj {
    fn from(value: &str) -> Self {
        Vobj::Str(String::from(value))
     } 
 }
 
+impl From<VMError> for Vobj {
+    fn from(error: VMError) -> Self {
+        match error {
+            VMError::ThrownErr(value) => value,
+            error => Vobj::Error(Box::new(ErrorValue {
+                kind: error.kind().to_string(),
+                message: error.into(),
+            })),
+        }
+    }
+}
+
+impl PartialEq for Vobj {
+    fn eq(&self, other: &Self) -> bool {
+        self.compare(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Vobj {}
+
+impl PartialOrd for Vobj {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Vobj {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.compare(other)
+    }
+}
+
+// Numbers equal to an Int hash as that Int, so equal values hash alike.
+impl Hash for Vobj {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.rank().hash(state);
+        match self {
+            Vobj::Int(value) => value.hash(state),
+            Vobj::Double(value) => match exact_int(*value) {
+                Some(value) => value.hash(state),
+                None if value.is_nan() => f64::NAN.to_bits().hash(state),
+                None => value.to_bits().hash(state),
+            },
+            Vobj::Str(value) => value.hash(state),
+            Vobj::Bool(value) => value.hash(state),
+            Vobj::Error(error) => error.hash(state),
+            Vobj::List(values) => values.hash(state),
+            Vobj::Range(range) => range.hash(state),
+            Vobj::Iter(iter) => iter.hash(state),
+            Vobj::Null => {}
+        }
+    }
+}
+
 impl fmt::Display for Vobj {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -308,6 +1056,24 @@ impl fmt::Display for Vobj {
             Self::Double(value) => write!(f, "{}", value),
             Self::Int(value) => write!(f, "{}", value),
             Self::Null => write!(f, ""),
+            Self::Error(error) => write!(f, "{}: {}", error.kind, error.message),
+            Self::Bool(value) => write!(f, "{}", value),
+            Self::List(values) => {
+                write!(f, "[")?;
+                for (index, value) in values.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ", ")?;
+                    }
+                    match value {
+                        Self::Str(value) => write!(f, "{:?}", value)?,
+                        Self::Null => write!(f, "null")?,
+                        value => write!(f, "{}", value)?,
+                    }
+                }
+                write!(f, "]")
+            }
+            Self::Range(range) => write!(f, "{}", range),
+            Self::Iter(_) => write!(f, "<iterator>"),
         }
     }
 }
@@ -0,0 +1,81 @@
+use vm::asm::assemble;
+use vm::err::{Position, VMError};
+use vm::vm::Vm;
+use vm::vobj::{Vobj, VobjKind};
+
+fn int(value: i64) -> Vobj {
+    Vobj::Int(value)
+}
+
+fn double(value: f64) -> Vobj {
+    Vobj::Double(value)
+}
+
+#[test]
+fn rem_and_mod_signs(){
+    assert_eq!(Ok(int(-1)), Vobj::rem(&int(-7), &int(3)));
+    assert_eq!(Ok(int(2)), Vobj::modulo(&int(-7), &int(3)));
+    assert_eq!(Ok(int(1)), Vobj::rem(&int(7), &int(-3)));
+    assert_eq!(Ok(int(-2)), Vobj::modulo(&int(7), &int(-3)));
+    assert_eq!(Ok(double(-1.5)), Vobj::rem(&double(-7.5), &int(3)));
+    assert_eq!(Ok(double(1.5)), Vobj::modulo(&double(-7.5), &int(3)));
+    assert_eq!(Ok(int(0)), Vobj::modulo(&int(i64::MIN), &int(-1)));
+    assert_eq!(Err(VMError::OverflowErr("Rem")), Vobj::rem(&int(i64::MIN), &int(-1)));
+    assert_eq!(Err(VMError::DivisionByZeroErr), Vobj::rem(&int(1), &int(0)));
+    assert_eq!(Err(VMError::DivisionByZeroErr), Vobj::modulo(&double(1.0), &double(0.0)));
+}
+
+#[test]
+fn floor_and_true_division(){
+    assert_eq!(Ok(int(-4)), Vobj::floor_div(&int(-7), &int(2)));
+    assert_eq!(Ok(int(3)), Vobj::floor_div(&int(7), &int(2)));
+    assert_eq!(Ok(double(-4.0)), Vobj::floor_div(&double(-7.0), &int(2)));
+    assert_eq!(Ok(double(3.5)), Vobj::true_div(&int(7), &int(2)));
+    assert_eq!(Err(VMError::OverflowErr("FloorDiv")), Vobj::floor_div(&int(i64::MIN), &int(-1)));
+    assert_eq!(Err(VMError::DivisionByZeroErr), Vobj::floor_div(&int(1), &int(0)));
+    assert_eq!(Err(VMError::DivisionByZeroErr), Vobj::true_div(&double(1.0), &int(0)));
+}
+
+#[test]
+fn pow(){
+    assert_eq!(Ok(int(1024)), Vobj::pow(&int(2), &int(10)));
+    assert_eq!(Ok(double(0.25)), Vobj::pow(&int(2), &int(-2)));
+    assert_eq!(Ok(double(3.0)), Vobj::pow(&double(9.0), &double(0.5)));
+    assert_eq!(Err(VMError::OverflowErr("Pow")), Vobj::pow(&int(2), &int(64)));
+    assert_eq!(Err(VMError::DivisionByZeroErr), Vobj::pow(&int(0), &int(-1)));
+    assert_eq!(Ok(int(0)), Vobj::pow(&int(0), &int(i64::MAX)));
+    assert_eq!(Ok(int(1)), Vobj::pow(&int(1), &int(1 << 40)));
+    assert_eq!(Ok(int(1)), Vobj::pow(&int(-1), &int(1 << 40)));
+    assert_eq!(Ok(int(-1)), Vobj::pow(&int(-1), &int((1 << 40) + 1)));
+    assert_eq!(Ok(int(1)), Vobj::pow(&int(0), &int(0)));
+    assert_eq!(Err(VMError::OverflowErr("Pow")), Vobj::pow(&int(2), &int(1 << 40)));
+}
+
+#[test]
+fn neg_and_abs(){
+    assert_eq!(Ok(int(-3)), Vobj::neg(&int(3)));
+    assert_eq!(Ok(double(2.5)), Vobj::abs(&double(-2.5)));
+    assert_eq!(Err(VMError::OverflowErr("Neg")), Vobj::neg(&int(i64::MIN)));
+    assert_eq!(Err(VMError::OverflowErr("Abs")), Vobj::abs(&int(i64::MIN)));
+    assert_eq!(
+        Err(VMError::TypeErr { operation: "Neg", position: Position::Only, expected: vec![VobjKind::Int, VobjKind::Double], actual: VobjKind::Str }),
+        Vobj::neg(&Vobj::from("a"))
+    );
+    assert_eq!(
+        "Pow: left operand is Null, expected Int or Double",
+        Vobj::pow(&Vobj::Null, &int(1)).unwrap_err().to_string()
+    );
+}
+
+#[test]
+fn arithmetic_instructions(){
+    let text = "load -7\nload 2\nfloordiv\nload 3\nmod\nload 2\npow\nneg\nabs\nload 2\ntruediv\nload 5\nload 3\nrem\n";
+    let vm = Vm::load(assemble(text, "test.zt").unwrap());
+    vm.run().unwrap();
+    assert_eq!(vec![double(2.0), int(2)], *vm.dump_mem());
+
+    let vm = Vm::load(assemble("load 1\nneg\nneg\nload 0\nmod\n", "test.zt").unwrap());
+    assert_eq!(VMError::DivisionByZeroErr, vm.run().unwrap_err().error);
+    let vm = Vm::load(assemble("abs\n", "test.zt").unwrap());
+    assert_eq!(VMError::StackUnderflowErr(1), vm.run().unwrap_err().error);
+}
@@ -0,0 +1,67 @@
+use vm::asm::*;
+use vm::bytecode;
+use vm::err::Source;
+use vm::instruction::Instruction;
+use vm::vm::Vm;
+use vm::vobj::Vobj;
+
+#[test]
+fn assemble_labels(){
+    let text = "start: load 1\n  load \"a;b\" ; comment\n\nbr start\n";
+    let code = assemble(text, "test.zt").unwrap();
+    let expected = vec![
+        Instruction::load_instruction(Vobj::Int(1), Source::with_span("test.zt", 1, 8, 6)),
+        Instruction::load_instruction(Vobj::from("a;b"), Source::with_span("test.zt", 2, 3, 10)),
+        Instruction::br(0, Source::with_span("test.zt", 4, 1, 8)),
+    ];
+    assert_eq!(expected, code);
+}
+
+#[test]
+fn assemble_error_line(){
+    let result = assemble("load 1\nload 2\nfrobnicate\n", "test.zt");
+    let error = result.unwrap_err();
+    assert_eq!(3, error.line);
+    assert_eq!("unknown instruction `frobnicate`", error.message);
+}
+
+#[test]
+fn assemble_unknown_label(){
+    let error = assemble("bgt nowhere", "test.zt").unwrap_err();
+    assert_eq!("unknown label `nowhere`", error.message);
+}
+
+#[test]
+fn assemble_and_run(){
+    let code = assemble("load 1.5\nload 2\nmul\n", "test.zt").unwrap();
+    let vm = Vm::load(code);
+    vm.run().unwrap();
+    assert_eq!(vec![Vobj::Double(3.0)], *vm.dump_mem());
+}
+
+#[test]
+fn disassemble_round_trip(){
+    let text = "load \"x\\n\\\"y\\\"\"\nload 2.0\nload null\nadd\nble 0\n";
+    let code = assemble(text, "test.zt").unwrap();
+    let listing = disassemble(&code);
+    let reassembled: String = listing
+        .lines()
+        .map(|line| format!("{}\n", &line[6..]))
+        .collect();
+    assert_eq!(code, assemble(&reassembled, "test.zt").unwrap());
+}
+
+#[test]
+fn bytecode_round_trip(){
+    let code = assemble("load -3\nload 0.25\nload \"s\"\nload null\ndiv\nbnq 1\ngstore x\n", "test.zt").unwrap();
+    let bytes = bytecode::encode(&code);
+    assert!(bytecode::is_bytecode(&bytes));
+    assert_eq!(code, bytecode::decode(&bytes).unwrap());
+}
+
+#[test]
+fn bytecode_truncated(){
+    let code = assemble("load 1\nadd\n", "test.zt").unwrap();
+    let bytes = bytecode::encode(&code);
+    assert!(bytecode::decode(&bytes[..bytes.len() - 1]).is_err());
+}
@@ -0,0 +1,55 @@
+use vm::asm::assemble_program;
+use vm::err::{BacktraceFrame, BranchRecord, Source};
+use vm::vm::{Vm, BRANCH_HISTORY};
+use vm::vobj::Vobj;
+
+#[test]
+fn error_carries_frames_and_state(){
+    let text = "main: call outer\n       br end\nouter: load 1\n       call inner\n       ret\ninner: load 0\n       div\n       ret\nend:   nop\n";
+    let program = assemble_program(text, "test.zt").unwrap();
+    let vm = Vm::new(program.into());
+    let error = vm.run().unwrap_err();
+
+    assert_eq!("Division by zero error", error.error.to_string());
+    assert_eq!(6, error.pc);
+    assert_eq!("Div", error.instruction);
+    assert_eq!(Source::with_span("test.zt", 7, 8, 3), error.src);
+    assert_eq!(Vec::<Vobj>::new(), error.stack);
+    assert_eq!(
+        vec![
+            BacktraceFrame { name: "inner".to_string(), call_pc: 3, src: Source::with_span("test.zt", 4, 8, 10) },
+            BacktraceFrame { name: "outer".to_string(), call_pc: 0, src: Source::with_span("test.zt", 1, 7, 10) },
+        ],
+        error.backtrace.frames
+    );
+    assert_eq!(
+        vec![
+            BranchRecord { pc: 0, target: 2, src: Source::with_span("test.zt", 1, 7, 10) },
+            BranchRecord { pc: 3, target: 5, src: Source::with_span("test.zt", 4, 8, 10) },
+        ],
+        error.backtrace.branches
+    );
+    assert_eq!("Division by zero error -> 6 Div (test.zt:7:8)", error.to_string());
+    assert_eq!(
+        "frames:\n  0: inner, called from 3 (test.zt:4:8)\n  1: outer, called from 0 (test.zt:1:7)\nrecent branches:\n  0 -> 2 (test.zt:1:7)\n  3 -> 5 (test.zt:4:8)\n",
+        error.backtrace.to_string()
+    );
+}
+
+#[test]
+fn branch_history_is_bounded(){
+    let text = "load 0\nloop: load 1\nadd\ngload x\nnop\nbr loop\n";
+    let program = assemble_program(text, "test.zt").unwrap();
+    let vm = Vm::new(program.into());
+    let error = vm.run().unwrap_err();
+    assert_eq!("undefined global `x`", error.error.to_string());
+    assert_eq!(vec![Vobj::Int(1)], error.stack);
+    assert!(error.backtrace.is_empty());
+
+    let mut vm = Vm::new(assemble_program("load 1\nloop: nop\nbr loop\n", "test.zt").unwrap().into());
+    vm.set_fuel(Some(1000));
+    let error = vm.run().unwrap_err();
+    assert_eq!("out of fuel", error.error.to_string());
+    assert_eq!(BRANCH_HISTORY, error.backtrace.branches.len());
+    assert!(error.backtrace.branches.iter().all(|branch| branch.pc == 2 && branch.target == 1));
+}
@@ -0,0 +1,47 @@
+use vm::asm::assemble;
+use vm::err::{Position, VMError};
+use vm::vm::Vm;
+use vm::vobj::{Vobj, VobjKind};
+
+fn int(value: i64) -> Vobj {
+    Vobj::Int(value)
+}
+
+#[test]
+fn logic(){
+    assert_eq!(Ok(int(0b1000)), Vobj::bit_and(&int(0b1100), &int(0b1010)));
+    assert_eq!(Ok(int(0b1110)), Vobj::bit_or(&int(0b1100), &int(0b1010)));
+    assert_eq!(Ok(int(0b0110)), Vobj::bit_xor(&int(0b1100), &int(0b1010)));
+    assert_eq!(Ok(int(-1)), Vobj::bit_not(&int(0)));
+    assert_eq!(Ok(int(64)), Vobj::popcount(&int(-1)));
+    assert_eq!(Ok(int(3)), Vobj::popcount(&int(0b1011)));
+}
+
+#[test]
+fn shifts(){
+    assert_eq!(Ok(int(40)), Vobj::shl(&int(5), &int(3)));
+    assert_eq!(Ok(int(i64::MIN)), Vobj::shl(&int(1), &int(63)));
+    assert_eq!(Ok(int(-2)), Vobj::sar(&int(-8), &int(2)));
+    assert_eq!(Ok(int(0x3FFF_FFFF_FFFF_FFFE)), Vobj::shr(&int(-8), &int(2)));
+    assert_eq!(Err(VMError::InvalidShiftErr(64)), Vobj::shl(&int(1), &int(64)));
+    assert_eq!(Err(VMError::InvalidShiftErr(-1)), Vobj::shr(&int(1), &int(-1)));
+    assert_eq!("shift amount 64 is out of range 0..64", VMError::InvalidShiftErr(64).to_string());
+}
+
+#[test]
+fn int_only(){
+    assert_eq!(
+        Err(VMError::TypeErr { operation: "And", position: Position::Right, expected: vec![VobjKind::Int], actual: VobjKind::Double }),
+        Vobj::bit_and(&int(1), &Vobj::Double(1.0))
+    );
+    assert_eq!("Shl: left operand is Str, expected Int", Vobj::shl(&Vobj::from("1"), &int(1)).unwrap_err().to_string());
+    assert_eq!("Not: operand is Null, expected Int", Vobj::bit_not(&Vobj::Null).unwrap_err().to_string());
+}
+
+#[test]
+fn bit_instructions(){
+    let text = "load 255\nload 15\nxor\nload 2\nsar\nload 1\nshl\nload 3\nor\nnot\nload 0\nnot\nload 60\nshr\nand\npopcount\n";
+    let vm = Vm::load(assemble(text, "test.zt").unwrap());
+    vm.run().unwrap();
+    assert_eq!(vec![int(1)], *vm.dump_mem());
+}
@@ -0,0 +1,76 @@
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+
+use vm::asm::assemble;
+use vm::err::ConfigError;
+use vm::vm::*;
+use vm::vobj::Vobj;
+
+#[derive(Clone, Default)]
+struct SharedOutput(Arc<Mutex<Vec<u8>>>);
+
+impl Write for SharedOutput {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn builder_initial_stack(){
+    let code = assemble("add\n", "test.zt").unwrap();
+    let vm = Vm::builder(code)
+        .stack(vec![Vobj::Int(40), Vobj::Int(2)])
+        .build()
+        .unwrap();
+    vm.run().unwrap();
+    assert_eq!(vec![Vobj::Int(42)], *vm.dump_mem());
+}
+
+#[test]
+fn builder_output(){
+    let code = assemble("load \"hello\"\nprint\nload 1.5\nprint\n", "test.zt").unwrap();
+    let output = SharedOutput::default();
+    let vm = Vm::builder(code)
+        .output(Box::new(output.clone()))
+        .build()
+        .unwrap();
+    vm.run().unwrap();
+    assert_eq!("hello\n1.5\n", String::from_utf8(output.0.lock().unwrap().clone()).unwrap());
+}
+
+#[test]
+fn builder_fuel_and_limits(){
+    let code = assemble("x: load 1\nbr x\n", "test.zt").unwrap();
+    let vm = Vm::builder(code)
+        .fuel(10)
+        .max_stack_depth(100)
+        .build()
+        .unwrap();
+    assert_eq!(Ok(RunOutcome::OutOfFuel), vm.run_budgeted());
+    assert_eq!(100, vm.config().max_stack_depth);
+    assert_eq!(5, vm.dump_mem().len());
+}
+
+#[test]
+fn builder_validation(){
+    let code = assemble("nop\n", "test.zt").unwrap();
+    let result = Vm::builder(code.clone())
+        .stack(vec![Vobj::Int(1), Vobj::Int(2)])
+        .max_stack_depth(1)
+        .build();
+    assert_eq!(Some(ConfigError::StackTooDeep { len: 2, max: 1 }), result.err());
+    let result = Vm::builder(code.clone())
+        .stack(vec![Vobj::from("too long")])
+        .max_heap_bytes(4)
+        .build();
+    assert!(matches!(result, Err(ConfigError::HeapTooLarge { max: 4, .. })));
+    let error = Vm::builder(code.clone()).max_call_depth(0).build().err();
+    assert_eq!(Some(ConfigError::ZeroCallDepth), error);
+    let error = Vm::builder(code).max_stack_depth(0).build().err().unwrap();
+    assert_eq!(ConfigError::ZeroStackDepth, error);
+    assert_eq!("max stack depth must be at least 1", error.to_string());
+}
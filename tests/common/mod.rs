@@ -0,0 +1,17 @@
+use vm::asm::assemble;
+use vm::vm::Vm;
+use vm::vobj::Vobj;
+
+/// Assembles and runs `text`, returning the vm for inspection.
+pub fn run_vm(text: &str) -> Vm {
+    let vm = Vm::load(assemble(text, "test.zt").unwrap());
+    vm.run().unwrap();
+    vm
+}
+
+/// Assembles and runs `text`, returning the final stack.
+pub fn run(text: &str) -> Vec<Vobj> {
+    let vm = run_vm(text);
+    let stack = vm.dump_mem().clone();
+    stack
+}
@@ -0,0 +1,99 @@
+use std::cmp::Ordering;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashSet;
+use std::hash::{Hash, Hasher};
+
+use vm::vobj::Vobj;
+
+mod common;
+use common::run;
+
+fn hash(value: &Vobj) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[test]
+fn numeric_equality(){
+    assert_eq!(Vobj::Int(1), Vobj::Double(1.0));
+    assert_eq!(Vobj::Double(0.0), Vobj::Double(-0.0));
+    assert_ne!(Vobj::Int(1), Vobj::Double(1.5));
+    assert_ne!(Vobj::Int(1), Vobj::from("1"));
+    assert_ne!(Vobj::Int(0), Vobj::Null);
+    // 2^53 + 1 has no exact double
+    assert_ne!(Vobj::Int((1 << 53) + 1), Vobj::Double((1u64 << 53) as f64));
+    assert_eq!(Ordering::Greater, Vobj::Int((1 << 53) + 1).compare(&Vobj::Double((1u64 << 53) as f64)));
+    assert_eq!(Ordering::Less, Vobj::Int(i64::MAX).compare(&Vobj::Double(9.3e18)));
+    assert_eq!(Ordering::Greater, Vobj::Int(-2).compare(&Vobj::Double(-2.5)));
+}
+
+#[test]
+fn nan_is_the_largest_number(){
+    let nan = Vobj::Double(f64::NAN);
+    assert_eq!(nan, Vobj::Double(f64::NAN));
+    assert!(nan > Vobj::Double(f64::INFINITY));
+    assert!(nan > Vobj::Int(i64::MAX));
+    assert!(nan < Vobj::from(""));
+}
+
+#[test]
+fn total_order_across_kinds(){
+    let mut values = vec![
+        Vobj::from("b"),
+        Vobj::Double(f64::NAN),
+        Vobj::Int(2),
+        Vobj::Bool(true),
+        Vobj::from("a"),
+        Vobj::Double(1.5),
+        Vobj::Null,
+        Vobj::Bool(false),
+        Vobj::Double(f64::NEG_INFINITY),
+    ];
+    values.sort();
+    let expected = vec![
+        Vobj::Null,
+        Vobj::Bool(false),
+        Vobj::Bool(true),
+        Vobj::Double(f64::NEG_INFINITY),
+        Vobj::Double(1.5),
+        Vobj::Int(2),
+        Vobj::Double(f64::NAN),
+        Vobj::from("a"),
+        Vobj::from("b"),
+    ];
+    assert_eq!(expected, values);
+}
+
+#[test]
+fn hash_agrees_with_equality(){
+    assert_eq!(hash(&Vobj::Int(3)), hash(&Vobj::Double(3.0)));
+    assert_eq!(hash(&Vobj::Double(0.0)), hash(&Vobj::Double(-0.0)));
+    assert_eq!(hash(&Vobj::Double(f64::NAN)), hash(&Vobj::Double(-f64::NAN)));
+    let set: HashSet<Vobj> = [Vobj::Int(1), Vobj::Double(1.0), Vobj::from("1"), Vobj::Double(1.5)].into_iter().collect();
+    assert_eq!(3, set.len());
+}
+
+#[test]
+fn branches_use_the_total_order(){
+    assert_eq!(vec![Vobj::from("equal")], run("load 1\nload 1.0\nbeq yes\nload \"different\"\nbr end\nyes: load \"equal\"\nend: nop\n"));
+    assert_eq!(vec![Vobj::from("less")], run("load \"apple\"\nload \"banana\"\nblt yes\nload \"not less\"\nbr end\nyes: load \"less\"\nend: nop\n"));
+    assert_eq!(vec![Vobj::from("less")], run("load null\nload 0\nblt yes\nload \"not less\"\nbr end\nyes: load \"less\"\nend: nop\n"));
+}
+
+#[test]
+fn ordering_helpers_use_the_total_order(){
+    assert_eq!(Ok(true), Vobj::less_than(&Vobj::from("apple"), &Vobj::from("banana")));
+    assert_eq!(Ok(true), Vobj::less_eq(&Vobj::Null, &Vobj::Int(1)));
+    assert_eq!(Ok(true), Vobj::greater_than(&Vobj::from("a"), &Vobj::Int(1)));
+    assert_eq!(Ok(true), Vobj::greater_eq(&Vobj::Int(2), &Vobj::Double(2.0)));
+    assert_eq!(Ok(false), Vobj::greater_than(&Vobj::Double(f64::NAN), &Vobj::Double(f64::NAN)));
+}
+
+#[test]
+fn cmp_instruction(){
+    assert_eq!(
+        vec![Vobj::Int(-1), Vobj::Int(0), Vobj::Int(1)],
+        run("load 1\nload 2\ncmp\nload 2\nload 2.0\ncmp\nload \"b\"\nload \"a\"\ncmp\n")
+    );
+}
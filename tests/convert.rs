@@ -0,0 +1,79 @@
+use vm::asm::assemble;
+use vm::err::{Position, VMError};
+use vm::vm::Vm;
+use vm::vobj::{Vobj, VobjKind};
+
+fn text(value: &str) -> Vobj {
+    Vobj::from(value)
+}
+
+#[test]
+fn to_int(){
+    assert_eq!(Ok(Vobj::Int(42)), Vobj::to_int(&text(" 42 ")));
+    assert_eq!(Ok(Vobj::Int(3)), Vobj::to_int(&text("3.7")));
+    assert_eq!(Ok(Vobj::Int(-2)), Vobj::to_int(&Vobj::Double(-2.9)));
+    assert_eq!(Ok(Vobj::Int(1)), Vobj::to_int(&Vobj::Bool(true)));
+    assert_eq!(
+        Err(VMError::ConversionErr { value: Vobj::Double(1e19), target: "Int" }),
+        Vobj::to_int(&Vobj::Double(1e19))
+    );
+    assert!(Vobj::to_int(&Vobj::Double(f64::NAN)).is_err());
+    assert_eq!("cannot convert \"abc\" to Int", Vobj::to_int(&text("abc")).unwrap_err().to_string());
+    assert_eq!(
+        Err(VMError::TypeErr {
+            operation: "ToInt",
+            position: Position::Only,
+            expected: vec![VobjKind::Int, VobjKind::Double, VobjKind::Str, VobjKind::Bool],
+            actual: VobjKind::Null,
+        }),
+        Vobj::to_int(&Vobj::Null)
+    );
+}
+
+#[test]
+fn to_double_and_str(){
+    assert_eq!(Ok(Vobj::Double(2.5)), Vobj::to_double(&text("2.5")));
+    assert_eq!(Ok(Vobj::Double(7.0)), Vobj::to_double(&Vobj::Int(7)));
+    assert_eq!(Ok(Vobj::Double(0.0)), Vobj::to_double(&Vobj::Bool(false)));
+    assert_eq!("cannot convert \"\" to Double", Vobj::to_double(&text("")).unwrap_err().to_string());
+    assert_eq!(Ok(text("12")), Vobj::to_str(&Vobj::Int(12)));
+    assert_eq!(Ok(text("0.5")), Vobj::to_str(&Vobj::Double(0.5)));
+    assert_eq!(Ok(text("true")), Vobj::to_str(&Vobj::Bool(true)));
+    assert_eq!(Ok(text("")), Vobj::to_str(&Vobj::Null));
+}
+
+#[test]
+fn to_bool(){
+    assert_eq!(Ok(Vobj::Bool(true)), Vobj::to_bool(&Vobj::Int(-3)));
+    assert_eq!(Ok(Vobj::Bool(false)), Vobj::to_bool(&Vobj::Double(0.0)));
+    assert_eq!(Ok(Vobj::Bool(false)), Vobj::to_bool(&Vobj::Null));
+    assert_eq!(Ok(Vobj::Bool(true)), Vobj::to_bool(&text("TRUE")));
+    assert_eq!(Ok(Vobj::Bool(false)), Vobj::to_bool(&text(" false")));
+    assert_eq!("cannot convert \"yes\" to Bool", Vobj::to_bool(&text("yes")).unwrap_err().to_string());
+}
+
+#[test]
+fn parse_num(){
+    assert_eq!(Ok(Vobj::Int(-17)), Vobj::parse_num(&text("-17")));
+    assert_eq!(Ok(Vobj::Double(1.5e3)), Vobj::parse_num(&text("1.5e3")));
+    assert_eq!(Ok(Vobj::Double(1e19)), Vobj::parse_num(&text("10000000000000000000")));
+    assert_eq!("cannot convert \"1,5\" to a number", Vobj::parse_num(&text("1,5")).unwrap_err().to_string());
+    assert_eq!("ParseNum: operand is Int, expected Str", Vobj::parse_num(&Vobj::Int(1)).unwrap_err().to_string());
+}
+
+#[test]
+fn conversion_instructions(){
+    let source = "load \"40\"\nparsenum\nload \"2.9\"\ntoint\nadd\ntostr\nload 1\ntobool\nload 3\ntodouble\n";
+    let vm = Vm::load(assemble(source, "test.zt").unwrap());
+    vm.run().unwrap();
+    assert_eq!(vec![text("42"), Vobj::Bool(true), Vobj::Double(3.0)], *vm.dump_mem());
+}
+
+#[test]
+fn conversion_error_is_catchable(){
+    let source = "trystart caught\nload \"x\"\ntoint\ntryend\ncaught: nop\n";
+    let vm = Vm::load(assemble(source, "test.zt").unwrap());
+    vm.run().unwrap();
+    let caught = vm.dump_mem()[0].clone();
+    assert_eq!("Conversion: cannot convert \"x\" to Int", caught.to_string());
+}
@@ -0,0 +1,81 @@
+use vm::asm::{assemble, assemble_program};
+use vm::diagnostic::{Diagnostic, DiagnosticKind};
+use vm::err::Source;
+use vm::instruction::Instruction;
+use vm::program::Program;
+use vm::vm::Vm;
+use vm::vobj::Vobj;
+
+#[test]
+fn render_runtime_error(){
+    let text = "main:\n    load 1\n    call f\n    br end\nf:  load \"a\"\n    load 0\n    div\nend: nop\n";
+    let program = assemble_program(text, "test.zt").unwrap();
+    let vm = Vm::new(program.into());
+    let error = vm.run().unwrap_err();
+    let diagnostic = Diagnostic::from(&*error);
+    let expected = "runtime error: Div: left operand is Str, expected Int or Double
+ --> test.zt:7:5
+  |
+7 |     div
+  |     ^^^
+  = stack: [1]
+  = backtrace:
+      0: f, called from 1 (test.zt:3:5)
+  = recent branches:
+      1 -> 3 (test.zt:3:5)
+";
+    assert_eq!(expected, diagnostic.render(text));
+}
+
+#[test]
+fn render_assembly_error(){
+    let text = "load 1\n\tfrob 2 ; oops\n";
+    let error = assemble(text, "test.zt").unwrap_err();
+    assert_eq!(Source::with_span("test.zt", 2, 2, 6), error.src);
+
+    let expected = "assembly error: unknown instruction `frob`
+ --> test.zt:2:2
+  |
+2 | \tfrob 2 ; oops
+  | \t^^^^^^
+";
+    assert_eq!(expected, Diagnostic::from(&error).render(text));
+}
+
+#[test]
+fn render_without_text(){
+    let diagnostic = Diagnostic::new(DiagnosticKind::Runtime, "boom", Source::new("gone.zt", 3));
+    assert_eq!("runtime error: boom\n --> gone.zt:3\n", diagnostic.render(""));
+    assert_eq!("runtime error: boom (gone.zt:3)", diagnostic.to_string());
+}
+
+#[test]
+fn render_whole_line(){
+    let diagnostic = Diagnostic::new(DiagnosticKind::Runtime, "boom", Source::new("test.zt", 10));
+    let text = "\n\n\n\n\n\n\n\n\n  load 1  \n";
+    let expected = "runtime error: boom
+  --> test.zt:10
+   |
+10 |   load 1  
+   |   ^^^^^^
+";
+    assert_eq!(expected, diagnostic.render(text));
+}
+
+#[test]
+fn verify_branch_targets(){
+    let text = "load 1\nload 2\nbgt 4\nbr 5\n";
+    let program = Program::new(assemble(text, "test.zt").unwrap());
+    let diagnostics = program.verify().unwrap_err();
+    assert_eq!(1, diagnostics.len());
+    assert_eq!(DiagnosticKind::Verification, diagnostics[0].kind);
+    assert_eq!(Source::with_span("test.zt", 4, 1, 4), diagnostics[0].src);
+    assert!(diagnostics[0].render(text).contains("4 | br 5\n  | ^^^^\n"));
+
+    let program = Program::new(vec![
+        Instruction::load_instruction(Vobj::Int(1), Source::new("test.zt", 1)),
+        Instruction::call(0, Source::new("test.zt", 2)),
+        Instruction::br(3, Source::new("test.zt", 3)),
+    ]);
+    assert!(program.verify().is_ok());
+}
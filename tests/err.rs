@@ -0,0 +1,80 @@
+use std::error::Error;
+use std::io::{self, Write};
+
+use vm::asm::assemble;
+use vm::err::{Limit, Position, VMError};
+use vm::vm::Vm;
+use vm::vobj::{Vobj, VobjKind};
+
+struct BrokenOutput;
+
+impl Write for BrokenOutput {
+    fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+        Err(io::Error::new(io::ErrorKind::BrokenPipe, "pipe closed"))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn error_display(){
+    let type_error = VMError::TypeErr {
+        operation: "Add",
+        position: Position::Right,
+        expected: vec![VobjKind::Int, VobjKind::Double],
+        actual: VobjKind::Str,
+    };
+    assert_eq!("Add: right operand is Str, expected Int or Double", type_error.to_string());
+    assert_eq!("integer overflow in Add", VMError::OverflowErr("Add").to_string());
+    assert_eq!("index 4 is out of range for length 2", VMError::IndexOutOfRangeErr { index: 4, len: 2 }.to_string());
+    assert_eq!("unknown native function `nope`", VMError::UnknownNativeErr("nope".to_string()).to_string());
+    assert_eq!("at least 2 arguments are required", VMError::StackUnderflowErr(2).to_string());
+    assert_eq!("limit exceeded: call depth is limited to 4 frames", VMError::LimitExceededErr(Limit::CallDepth(4)).to_string());
+    let message: String = VMError::DivisionByZeroErr.into();
+    assert_eq!(message, VMError::DivisionByZeroErr.to_string());
+}
+
+#[test]
+fn checked_int_arithmetic(){
+    assert_eq!(Err(VMError::OverflowErr("Add")), Vobj::add(&Vobj::Int(i64::MAX), &Vobj::Int(1)));
+    assert_eq!(Err(VMError::OverflowErr("Sub")), Vobj::sub(&Vobj::Int(i64::MIN), &Vobj::Int(1)));
+    assert_eq!(Err(VMError::OverflowErr("Mul")), Vobj::mul(&Vobj::Int(i64::MAX), &Vobj::Int(2)));
+    assert_eq!(Err(VMError::OverflowErr("Div")), Vobj::div(&Vobj::Int(i64::MIN), &Vobj::Int(-1)));
+    assert_eq!(
+        Err(VMError::TypeErr {
+            operation: "Add",
+            position: Position::Right,
+            expected: vec![VobjKind::Int, VobjKind::Double],
+            actual: VobjKind::Null,
+        }),
+        Vobj::add(&Vobj::Int(1), &Vobj::Null)
+    );
+}
+
+#[test]
+fn io_error_is_chained(){
+    let code = assemble("load 1\nprint\n", "test.zt").unwrap();
+    let vm = Vm::builder(code).output(Box::new(BrokenOutput)).build().unwrap();
+    let error = vm.run().unwrap_err();
+    assert!(matches!(error.error, VMError::IoErr(_)));
+    assert_eq!("i/o error: pipe closed", error.error.to_string());
+
+    let vm_error = error.source().unwrap();
+    assert_eq!("i/o error: pipe closed", vm_error.to_string());
+    let io_error = vm_error.source().unwrap().downcast_ref::<io::Error>().unwrap();
+    assert_eq!(io::ErrorKind::BrokenPipe, io_error.kind());
+}
+
+#[test]
+fn errors_compose_with_question_mark(){
+    fn host() -> Result<Vec<Vobj>, Box<dyn Error>> {
+        let vm = Vm::load(assemble("load 1\nload 0\ndiv\n", "test.zt")?);
+        vm.run()?;
+        let stack = vm.dump_mem().clone();
+        Ok(stack)
+    }
+    let error = host().unwrap_err();
+    assert!(error.to_string().starts_with("Division by zero error -> 2 Div"));
+}
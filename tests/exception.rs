@@ -0,0 +1,95 @@
+use vm::asm::assemble;
+use vm::err::VMError;
+use vm::vm::Vm;
+use vm::vobj::{ErrorValue, Vobj};
+
+mod common;
+use common::{run, run_vm};
+
+fn error(kind: &str, message: &str) -> Vobj {
+    Vobj::Error(Box::new(ErrorValue { kind: kind.to_string(), message: message.to_string() }))
+}
+
+#[test]
+fn catch_builtin_error(){
+    let stack = run("
+        load \"kept\"
+        trystart handler
+        load 1
+        load 2
+        load 0
+        div
+        tryend
+        br end
+handler: load \"recovered\"
+end:    nop
+    ");
+    assert_eq!(
+        vec![Vobj::from("kept"), error("DivisionByZero", "Division by zero error"), Vobj::from("recovered")],
+        stack
+    );
+}
+
+#[test]
+fn catch_thrown_value_across_frames(){
+    let vm = run_vm("
+        trystart handler
+        call fail
+        tryend
+        br end
+fail:   load 1
+        load \"bad data\"
+        throw
+        ret
+handler: nop
+end:    nop
+    ");
+    assert_eq!(vec![Vobj::from("bad data")], *vm.dump_mem());
+    assert!(vm.frames().is_empty());
+}
+
+#[test]
+fn nested_handlers_and_try_end(){
+    let stack = run("
+        trystart outer
+        trystart inner
+        tryend
+        load 7
+        throw
+inner:  load \"inner\"
+        br end
+outer:  load \"outer\"
+end:    nop
+    ");
+    assert_eq!(vec![Vobj::Int(7), Vobj::from("outer")], stack);
+}
+
+#[test]
+fn handler_does_not_outlive_its_call(){
+    let vm = Vm::load(assemble("
+        call guarded
+        load 1
+        throw
+guarded: trystart handler
+        ret
+handler: nop
+    ", "test.zt").unwrap());
+    let error = vm.run().unwrap_err();
+    assert_eq!("uncaught exception: 1", error.error.to_string());
+    assert!(error.stack.is_empty());
+}
+
+#[test]
+fn uncaught_and_unmatched(){
+    let vm = Vm::load(assemble("tryend", "test.zt").unwrap());
+    assert_eq!("try end without a matching try start", vm.run().unwrap_err().error.to_string());
+
+    let vm = Vm::load(assemble("trystart 5", "test.zt").unwrap());
+    assert_eq!("invalid instruction index 5: out of bound", vm.run().unwrap_err().error.to_string());
+
+    assert_eq!(Vobj::Int(3), Vobj::from(VMError::ThrownErr(Vobj::Int(3))));
+    assert_eq!(
+        error("UndefinedGlobal", "undefined global `x`"),
+        Vobj::from(VMError::UndefinedGlobalErr("x".to_string()))
+    );
+}
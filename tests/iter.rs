@@ -0,0 +1,90 @@
+use vm::asm::assemble;
+use vm::bytecode;
+use vm::err::Source;
+use vm::instruction::Instruction;
+use vm::vm::Vm;
+use vm::vobj::{IterState, Vobj};
+
+mod common;
+use common::run;
+
+fn collect(value: Vobj) -> Vec<Vobj> {
+    let mut state = match Vobj::iter(&value).unwrap() {
+        Vobj::Iter(state) => state,
+        other => panic!("expected an iterator, got {:?}", other),
+    };
+    std::iter::from_fn(|| state.next()).collect()
+}
+
+#[test]
+fn iterates_counts_strings_and_lists(){
+    assert_eq!(vec![Vobj::Int(0), Vobj::Int(1), Vobj::Int(2)], collect(Vobj::Int(3)));
+    assert!(collect(Vobj::Int(-2)).is_empty());
+    assert_eq!(vec![Vobj::from("h"), Vobj::from("é"), Vobj::from("!")], collect(Vobj::from("hé!")));
+    assert_eq!(vec![Vobj::Int(1), Vobj::from("a")], collect(Vobj::List(vec![Vobj::Int(1), Vobj::from("a")])));
+    assert_eq!("IterNew: operand is Double, expected Int or Str or List or Range or Iter", Vobj::iter(&Vobj::Double(1.0)).unwrap_err().to_string());
+}
+
+#[test]
+fn loop_over_count(){
+    let text = "\
+        load 0
+        gstore total
+        load 5
+        iternew
+loop:   iternext done
+        gload total
+        add
+        gstore total
+        br loop
+done:   gload total
+";
+    assert_eq!(vec![Vobj::Int(10)], run(text));
+}
+
+#[test]
+fn sums_a_list(){
+    let text = "\
+        load 0
+        gstore total
+        load [1, 2, 3.5]
+        iternew
+loop:   iternext done
+        gload total
+        add
+        gstore total
+        br loop
+done:   gload total
+";
+    assert_eq!(vec![Vobj::Double(6.5)], run(text));
+}
+
+#[test]
+fn exhausted_iterator_is_popped(){
+    assert_eq!(vec![Vobj::from("end")], run("load \"x\"\niternew\niternext end\ngstore c\niternext end\nload \"not reached\"\nend: load \"end\"\n"));
+    assert_eq!(vec![Vobj::from("end")], run("load 0\niternew\niternext end\nend: load \"end\"\n"));
+}
+
+#[test]
+fn iter_next_needs_an_iterator(){
+    let vm = Vm::load(assemble("load [1]\niternext end\nend: nop\n", "test.zt").unwrap());
+    assert_eq!("IterNext: operand is List, expected Iter", vm.run().unwrap_err().error.to_string());
+}
+
+#[test]
+fn iterators_round_trip_through_bytecode(){
+    let src = Source::new("test.zt", 1);
+    let states = [
+        IterState::Count { next: 2, end: 5 },
+        IterState::Chars { text: "hé".to_string(), offset: 1 },
+        IterState::Items { values: vec![Vobj::Int(1)], index: 1 },
+    ];
+    let code: Vec<Instruction> = states
+        .into_iter()
+        .map(|state| Instruction::load_instruction(Vobj::Iter(Box::new(state)), src.clone()))
+        .collect();
+    assert_eq!(code, bytecode::decode(&bytecode::encode(&code)).unwrap());
+
+    let broken = vec![Instruction::load_instruction(Vobj::Iter(Box::new(IterState::Chars { text: "é".to_string(), offset: 1 })), src)];
+    assert!(bytecode::decode(&bytecode::encode(&broken)).is_err());
+}
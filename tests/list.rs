@@ -0,0 +1,186 @@
+use vm::asm::{assemble, format_value, parse_value};
+use vm::bytecode;
+use vm::err::{Limit, Position, VMError};
+use vm::vm::{RunOutcome, Vm};
+use vm::vobj::{Vobj, VobjKind, MAX_LIST_DEPTH};
+
+mod common;
+use common::run;
+
+fn list(values: &[Vobj]) -> Vobj {
+    Vobj::List(values.to_vec())
+}
+
+fn ints(values: &[i64]) -> Vobj {
+    Vobj::List(values.iter().map(|value| Vobj::Int(*value)).collect())
+}
+
+#[test]
+fn literals(){
+    assert_eq!(Ok(ints(&[1, 2, 3])), parse_value("[1, 2, 3]"));
+    assert_eq!(Ok(list(&[])), parse_value("[ ]"));
+    let nested = list(&[Vobj::from("a, [b]"), ints(&[1]), Vobj::Null]);
+    assert_eq!(Ok(nested.clone()), parse_value("[\"a, [b]\", [1], null]"));
+    assert_eq!("[\"a, [b]\", [1], null]", format_value(&nested));
+    assert_eq!("[\"a, [b]\", [1], null]", nested.to_string());
+    assert!(parse_value("[1, 2").is_err());
+    assert!(parse_value("[1] 2").is_err());
+}
+
+#[test]
+fn bytecode_round_trip(){
+    let code = assemble("load [1, \"x\", [2.5, true]]\n", "test.zt").unwrap();
+    assert_eq!(code, bytecode::decode(&bytecode::encode(&code)).unwrap());
+}
+
+#[test]
+fn nesting_is_limited(){
+    let nested = |depth: usize| format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+    assert!(parse_value(&nested(MAX_LIST_DEPTH)).is_ok());
+    assert_eq!(Err("list literal nested deeper than 64 levels".to_string()), parse_value(&nested(MAX_LIST_DEPTH + 1)));
+    assert!(assemble(&format!("load {}\n", nested(100_000)), "test.zt").is_err());
+
+    // a crafted file nesting far too deep is rejected rather than overflowing the stack
+    let mut bytes = bytecode::encode(&assemble("load null\n", "test.zt").unwrap());
+    bytes.pop();
+    for _ in 0..2_000_000 {
+        bytes.extend_from_slice(&[6, 1, 0, 0, 0]);
+    }
+    bytes.push(0);
+    assert_eq!(Err("lists nested deeper than 64 levels".to_string()), bytecode::decode(&bytes));
+}
+
+#[test]
+fn make_list_len_get(){
+    assert_eq!(vec![ints(&[1, 2, 3])], run("load 1\nload 2\nload 3\nload 3\nmakelist\n"));
+    assert_eq!(vec![Vobj::Int(3), Vobj::Int(2)], run("load [4, 5, 6]\nlen\nload \"héé\"\nlen\nload 0\nmul\nadd\nload [7, 2]\nload 1\nget\n"));
+
+    let vm = Vm::load(assemble("load [1]\nload 1\nget\n", "test.zt").unwrap());
+    assert_eq!(VMError::IndexOutOfRangeErr { index: 1, len: 1 }, vm.run().unwrap_err().error);
+    let vm = Vm::load(assemble("load 1\nload 2\nmakelist\n", "test.zt").unwrap());
+    assert_eq!(VMError::StackUnderflowErr(3), vm.run().unwrap_err().error);
+}
+
+#[test]
+fn queries(){
+    let values = list(&[Vobj::Int(3), Vobj::from("a"), Vobj::Double(1.0), Vobj::Null]);
+    assert_eq!(Ok(Vobj::Bool(true)), Vobj::contains(&values, &Vobj::Int(1)));
+    assert_eq!(Ok(Vobj::Bool(false)), Vobj::contains(&values, &Vobj::from("b")));
+    assert_eq!(Ok(Vobj::Int(2)), Vobj::index_of(&values, &Vobj::Int(1)));
+    assert_eq!(Ok(Vobj::Null), Vobj::index_of(&values, &Vobj::Int(9)));
+    assert_eq!(Ok(Vobj::Null), Vobj::minimum(&values));
+    assert_eq!(Ok(Vobj::from("a")), Vobj::maximum(&values));
+    assert_eq!(Ok(Vobj::Null), Vobj::maximum(&list(&[])));
+}
+
+#[test]
+fn sum_and_join(){
+    assert_eq!(Ok(Vobj::Int(6)), Vobj::sum(&ints(&[1, 2, 3])));
+    assert_eq!(Ok(Vobj::Double(3.5)), Vobj::sum(&list(&[Vobj::Int(1), Vobj::Double(2.5)])));
+    assert_eq!(Ok(Vobj::Int(0)), Vobj::sum(&list(&[])));
+    assert_eq!(Err(VMError::OverflowErr("Sum")), Vobj::sum(&ints(&[i64::MAX, 1])));
+    assert_eq!(
+        Err(VMError::TypeErr { operation: "Sum", position: Position::Element(1), expected: vec![VobjKind::Int, VobjKind::Double], actual: VobjKind::Str }),
+        Vobj::sum(&list(&[Vobj::Int(1), Vobj::from("2")]))
+    );
+    assert_eq!(Ok(Vobj::from("1-a-true")), Vobj::join(&list(&[Vobj::Int(1), Vobj::from("a"), Vobj::Bool(true)]), &Vobj::from("-")));
+    assert_eq!("Join: left operand is Str, expected List", Vobj::join(&Vobj::from("ab"), &Vobj::from("")).unwrap_err().to_string());
+}
+
+#[test]
+fn sort_and_reverse(){
+    assert_eq!(
+        vec![list(&[Vobj::Null, Vobj::Int(1), Vobj::Double(2.5), Vobj::Int(10), Vobj::from("a")])],
+        run("load [10, \"a\", 2.5, null, 1]\nsort\n")
+    );
+    assert_eq!(vec![ints(&[3, 2, 1])], run("load [1, 2, 3]\nreverse\n"));
+}
+
+#[test]
+fn sort_by_key_subroutine(){
+    // sorts strings by length, keeping equal keys in order
+    let text = "\
+        load [\"ccc\", \"a\", \"bb\", \"d\"]
+        sortby key
+        br end
+key:    len
+        ret
+end:    nop
+";
+    assert_eq!(vec![list(&[Vobj::from("a"), Vobj::from("d"), Vobj::from("bb"), Vobj::from("ccc")])], run(text));
+}
+
+#[test]
+fn sort_by_key_errors(){
+    let text = "\
+        load 7
+        trystart caught
+        load [1, \"x\"]
+        sortby key
+        tryend
+        br end
+key:    load 1
+        add
+        ret
+caught: nop
+end:    nop
+";
+    let stack = run(text);
+    assert_eq!(2, stack.len());
+    assert_eq!(Vobj::Int(7), stack[0]);
+    assert_eq!("Type: Add: left operand is Str, expected Int or Double", stack[1].to_string());
+
+    // a key function has to leave its key on the stack
+    let vm = Vm::load(assemble("load [1]\nsortby key\nbr end\nkey: gstore tmp\nret\nend: nop\n", "test.zt").unwrap());
+    let error = vm.run().unwrap_err();
+    assert_eq!(VMError::StackUnderflowErr(1), error.error);
+    assert_eq!("SortBy 3", error.instruction);
+    assert_eq!(vec![ints(&[1])], error.stack);
+}
+
+#[test]
+fn nested_sort_by_hits_the_call_depth_limit(){
+    let text = "load [1]\nsortby key\nbr end\nkey: load [1]\nsortby key\nret\nend: nop\n";
+    let error = Vm::load(assemble(text, "test.zt").unwrap()).run().unwrap_err();
+    assert_eq!(VMError::LimitExceededErr(Limit::CallDepth(4096)), error.error);
+
+    // a key function may sort too
+    let text = "load [[2, 1], [1]]\nsortby key\nbr end\nkey: sortby inner\nlen\nret\ninner: ret\nend: nop\n";
+    assert_eq!(vec![list(&[ints(&[1]), ints(&[2, 1])])], run(text));
+}
+
+#[test]
+fn sort_by_resumes_after_refueling(){
+    let text = "\
+        load 0
+        gstore calls
+        trystart caught
+        load [5, 3, 1, 4, 2]
+        sortby key
+        tryend
+        br end
+key:    gload calls
+        load 1
+        add
+        gstore calls
+        neg
+        ret
+caught: load \"caught\"
+end:    nop
+";
+    let mut vm = Vm::builder(assemble(text, "test.zt").unwrap()).fuel(6).build().unwrap();
+    assert_eq!(Ok(RunOutcome::OutOfFuel), vm.run_budgeted());
+    assert_eq!(8, vm.pc());
+    assert_eq!(1, vm.frames().len());
+
+    // a little fuel at a time still makes progress, and each key is computed once
+    let mut refuels = 0;
+    while vm.run_budgeted() == Ok(RunOutcome::OutOfFuel) {
+        refuels += 1;
+        assert!(refuels < 100);
+        vm.add_fuel(1);
+    }
+    assert!(vm.is_finished());
+    assert_eq!(vec![ints(&[5, 4, 3, 2, 1])], *vm.dump_mem());
+    assert_eq!(Some(Vobj::Int(5)), vm.global("calls"));
+}
@@ -0,0 +1,98 @@
+use vm::asm::assemble;
+use vm::err::{Position, VMError};
+use vm::native::Natives;
+use vm::vm::{Vm, VmBuilder};
+use vm::vobj::{Vobj, VobjKind};
+
+mod common;
+use common::run;
+
+fn call(name: &str, args: &[Vobj]) -> Result<Vobj, VMError> {
+    Natives::standard().get(name).unwrap().call(args)
+}
+
+#[test]
+fn math(){
+    assert_eq!(Ok(Vobj::Double(3.0)), call("sqrt", &[Vobj::Int(9)]));
+    assert_eq!(Ok(Vobj::Double(2.0)), call("log10", &[Vobj::Double(100.0)]));
+    assert_eq!(Ok(Vobj::Double(0.0)), call("atan2", &[Vobj::Int(0), Vobj::Int(1)]));
+    assert_eq!(Ok(Vobj::Int(7)), call("floor", &[Vobj::Int(7)]));
+    assert_eq!(Ok(Vobj::Double(-3.0)), call("round", &[Vobj::Double(-2.5)]));
+    assert_eq!(Ok(Vobj::Double(2.0)), call("trunc", &[Vobj::Double(2.9)]));
+    assert_eq!(Ok(Vobj::Bool(true)), call("is_nan", &[Vobj::Double(f64::NAN)]));
+    assert_eq!(Ok(Vobj::Bool(false)), call("is_finite", &[Vobj::Double(f64::INFINITY)]));
+    assert_eq!(Ok(Vobj::Bool(true)), call("is_finite", &[Vobj::Int(1)]));
+}
+
+#[test]
+fn min_max_clamp(){
+    assert_eq!(Ok(Vobj::Int(2)), call("min", &[Vobj::Int(2), Vobj::Int(5)]));
+    assert_eq!(Ok(Vobj::Double(5.0)), call("max", &[Vobj::Double(2.0), Vobj::Int(5)]));
+    assert_eq!(Ok(Vobj::Int(10)), call("clamp", &[Vobj::Int(12), Vobj::Int(0), Vobj::Int(10)]));
+    assert_eq!(Ok(Vobj::Double(0.0)), call("clamp", &[Vobj::Int(-1), Vobj::Int(0), Vobj::Double(0.5)]));
+    // ints above 2^53 are compared exactly rather than through f64
+    let (big, bigger) = (9_007_199_254_740_992, 9_007_199_254_740_993);
+    assert_eq!(Ok(Vobj::Int(big)), call("min", &[Vobj::Int(bigger), Vobj::Int(big)]));
+    assert_eq!(Ok(Vobj::Int(bigger)), call("max", &[Vobj::Int(big), Vobj::Int(bigger)]));
+    assert_eq!(Ok(Vobj::Int(bigger)), call("clamp", &[Vobj::Int(i64::MAX), Vobj::Int(0), Vobj::Int(bigger)]));
+    assert_eq!(Ok(Vobj::Int(big)), call("clamp", &[Vobj::Int(0), Vobj::Int(big), Vobj::Int(bigger)]));
+    // NaN sorts above every number, whichever side it is passed on
+    assert_eq!(Ok(Vobj::Double(1.0)), call("min", &[Vobj::Double(f64::NAN), Vobj::Int(1)]));
+    assert_eq!(Ok(Vobj::Double(1.0)), call("min", &[Vobj::Int(1), Vobj::Double(f64::NAN)]));
+    for args in [[Vobj::Double(f64::NAN), Vobj::Int(1)], [Vobj::Int(1), Vobj::Double(f64::NAN)]] {
+        match call("max", &args) {
+            Ok(Vobj::Double(value)) => assert!(value.is_nan()),
+            other => panic!("expected NaN, got {:?}", other),
+        }
+    }
+    assert_eq!(
+        "incorrect argument error: clamp: lower bound is greater than upper bound",
+        call("clamp", &[Vobj::Int(1), Vobj::Int(2), Vobj::Int(0)]).unwrap_err().to_string()
+    );
+}
+
+#[test]
+fn argument_type_errors(){
+    assert_eq!(
+        Err(VMError::TypeErr { operation: "atan2", position: Position::Argument(2), expected: vec![VobjKind::Int, VobjKind::Double], actual: VobjKind::Str }),
+        call("atan2", &[Vobj::Int(1), Vobj::from("x")])
+    );
+    assert_eq!("sqrt: argument 1 is Null, expected Int or Double", call("sqrt", &[Vobj::Null]).unwrap_err().to_string());
+}
+
+#[test]
+fn native_instruction(){
+    assert_eq!(vec![Vobj::Double(5.0)], run("load 3\nload 3\nmul\nload 4\nload 4\nmul\nadd\nnative sqrt\n"));
+    assert_eq!(vec![Vobj::Int(3)], run("load 3\nload 9\nnative min\n"));
+    assert_eq!(vec![Vobj::Int(2), Vobj::Int(0)], run("load 2\nload 0\nload -5\nload 10\nnative clamp\n"));
+}
+
+#[test]
+fn unknown_and_underflow(){
+    let vm = Vm::load(assemble("load 1\nnative nope\n", "test.zt").unwrap());
+    let error = vm.run().unwrap_err();
+    assert_eq!(VMError::UnknownNativeErr("nope".to_string()), error.error);
+    assert_eq!("unknown native function `nope`", error.error.to_string());
+
+    let vm = Vm::load(assemble("load 1\nnative atan2\n", "test.zt").unwrap());
+    assert_eq!(VMError::StackUnderflowErr(2), vm.run().unwrap_err().error);
+    assert_eq!(vec![Vobj::Int(1)], *vm.dump_mem());
+}
+
+#[test]
+fn custom_natives(){
+    let program = assemble("load 2\nload 3\nnative hypot2\nload 1\nnative twice\n", "test.zt").unwrap();
+    let vm = VmBuilder::new(program)
+        .native("hypot2", 2, |args| Vobj::add(&Vobj::mul(&args[0], &args[0])?, &Vobj::mul(&args[1], &args[1])?))
+        .build()
+        .unwrap();
+    assert!(vm.natives().get("sqrt").is_some());
+    let mut vm = vm;
+    vm.register_native("twice", 1, |args| Vobj::add(&args[0], &args[0]));
+    vm.run().unwrap();
+    assert_eq!(vec![Vobj::Int(13), Vobj::Int(2)], *vm.dump_mem());
+
+    let program = assemble("load 4\nnative sqrt\n", "test.zt").unwrap();
+    let vm = VmBuilder::new(program).natives(Natives::new()).build().unwrap();
+    assert_eq!(VMError::UnknownNativeErr("sqrt".to_string()), vm.run().unwrap_err().error);
+}
@@ -0,0 +1,72 @@
+use vm::asm::assemble;
+use vm::err::VMError;
+use vm::vm::{Vm, VmBuilder, VmConfig};
+use vm::vobj::Vobj;
+
+mod common;
+use common::run;
+
+fn propagating(text: &str) -> Vm {
+    let config = VmConfig { null_propagation: true, ..VmConfig::default() };
+    VmBuilder::new(assemble(text, "test.zt").unwrap()).config(config).build().unwrap()
+}
+
+#[test]
+fn null_branches(){
+    let text = "load null\nbnull 3\nload \"not taken\"\nload 1\nbnull end\nload \"kept\"\nend: nop\n";
+    assert_eq!(vec![Vobj::from("kept")], run(text));
+    let text = "load 0\nbnnull 3\nload \"not taken\"\nload null\nbnnull end\nload \"kept\"\nend: nop\n";
+    assert_eq!(vec![Vobj::from("kept")], run(text));
+}
+
+#[test]
+fn coalesce(){
+    assert_eq!(vec![Vobj::Int(5)], run("load null\nload 5\ncoalesce\n"));
+    assert_eq!(vec![Vobj::Int(0)], run("load 0\nload 5\ncoalesce\n"));
+    assert_eq!(vec![Vobj::Null], run("load null\nload null\ncoalesce\n"));
+
+    let vm = Vm::load(assemble("load 1\ncoalesce\n", "test.zt").unwrap());
+    assert_eq!(VMError::StackUnderflowErr(2), vm.run().unwrap_err().error);
+}
+
+#[test]
+fn arithmetic_on_null_fails_by_default(){
+    let vm = Vm::load(assemble("load null\nload 1\nadd\n", "test.zt").unwrap());
+    assert_eq!("Add: left operand is Null, expected Int or Double", vm.run().unwrap_err().error.to_string());
+}
+
+#[test]
+fn null_propagation(){
+    let vm = propagating("load null\nload 1\nadd\nload 2\nmul\nload 3\nrem\nneg\n");
+    vm.run().unwrap();
+    assert_eq!(vec![Vobj::Null], *vm.dump_mem());
+
+    let vm = propagating("load 4\nload null\ndiv\nload 1\nload null\nshl\nload null\ntoint\n");
+    vm.run().unwrap();
+    assert_eq!(vec![Vobj::Null, Vobj::Null, Vobj::Null], *vm.dump_mem());
+
+    // non-null operands still have to be numbers
+    let vm = propagating("load \"a\"\nload 1\nadd\n");
+    assert!(vm.run().is_err());
+}
+
+#[test]
+fn collections_do_not_propagate_null(){
+    let vm = propagating("load [1, null]\nload null\ncontains\nload [1, null]\nload null\nindexof\nload null\nload 1\ncmp\n");
+    vm.run().unwrap();
+    assert_eq!(vec![Vobj::Bool(true), Vobj::Int(1), Vobj::Int(-1)], *vm.dump_mem());
+
+    let vm = propagating("load null\nlen\n");
+    assert!(vm.run().is_err());
+}
+
+#[test]
+fn comparisons_with_null_do_not_branch(){
+    let vm = propagating("load null\nload 1\nblt 5\nload 1\nload null\nbge 5\nload \"fell through\"\n");
+    vm.run().unwrap();
+    assert_eq!(vec![Vobj::from("fell through")], *vm.dump_mem());
+
+    let vm = propagating("load null\nload null\nbeq 4\nload \"not taken\"\nnop\n");
+    vm.run().unwrap();
+    assert!(vm.dump_mem().is_empty());
+}
@@ -0,0 +1,66 @@
+use vm::asm::assemble;
+use vm::vm::Vm;
+
+#[test]
+fn profile_counts(){
+    let code = assemble("load 1\nload 2\nadd\ncall f\nbr end\nf: load 3\nret\nend: nop\n", "test.zt").unwrap();
+    let mut vm = Vm::load(code);
+    let profiler = vm.enable_profiling();
+    vm.run().unwrap();
+    let report = profiler.report();
+
+    assert_eq!(8, report.instructions.len());
+    assert!(report.instructions.iter().all(|stat| stat.count == 1));
+    let load = report.opcodes.iter().find(|stat| stat.label == "Load").unwrap();
+    assert_eq!(3, load.count);
+    let line = report.lines.iter().find(|stat| stat.label == "test.zt:6").unwrap();
+    assert_eq!(1, line.count);
+    assert!(report.opcodes.windows(2).all(|pair| pair[0].time >= pair[1].time));
+}
+
+#[test]
+fn profile_folded_stacks(){
+    let code = assemble("call f\nbr end\nf: load 3\nret\nend: nop\n", "test.zt").unwrap();
+    let mut vm = Vm::load(code);
+    let profiler = vm.enable_profiling();
+    vm.run().unwrap();
+    let stacks: Vec<String> = profiler
+        .folded()
+        .lines()
+        .map(|line| line.rsplit_once(' ').unwrap().0.to_string())
+        .collect();
+    assert_eq!(vec!["main;Br", "main;Call", "main;Nop", "main;fn@2;Load", "main;fn@2;Ret"], stacks);
+}
+
+#[test]
+fn profile_folded_stacks_after_catch(){
+    let code = assemble("call outer\nbr end\nouter: trystart handler\ncall inner\nret\ninner: load \"bad data\"\nthrow\nhandler: ret\nend: nop\n", "test.zt").unwrap();
+    let mut vm = Vm::load(code);
+    let profiler = vm.enable_profiling();
+    vm.run().unwrap();
+    let stacks: Vec<String> = profiler
+        .folded()
+        .lines()
+        .map(|line| line.rsplit_once(' ').unwrap().0.to_string())
+        .collect();
+    assert_eq!(
+        vec!["main;Br", "main;Call", "main;Nop", "main;fn@2;Call", "main;fn@2;Ret", "main;fn@2;TryStart", "main;fn@2;fn@5;Load", "main;fn@2;fn@5;Throw"],
+        stacks
+    );
+}
+
+#[test]
+fn profile_counts_sort_by(){
+    let code = assemble("load [3, 1, 2]\nsortby key\nbr end\nkey: neg\nret\nend: nop\n", "test.zt").unwrap();
+    let mut vm = Vm::load(code);
+    let profiler = vm.enable_profiling();
+    vm.run().unwrap();
+    let report = profiler.report();
+
+    // sortby runs again after each key call returns
+    let sort_by = report.opcodes.iter().find(|stat| stat.label == "SortBy").unwrap();
+    assert_eq!(4, sort_by.count);
+    let neg = report.opcodes.iter().find(|stat| stat.label == "Neg").unwrap();
+    assert_eq!(3, neg.count);
+    assert!(profiler.folded().lines().any(|line| line.starts_with("main;fn@3;Neg ")));
+}
@@ -0,0 +1,50 @@
+use std::sync::Arc;
+use std::thread;
+
+use vm::asm::{assemble, assemble_program};
+use vm::program::Program;
+use vm::vm::*;
+use vm::vobj::Vobj;
+
+fn assert_send<T: Send>() {}
+
+#[test]
+fn vm_is_send(){
+    assert_send::<Vm>();
+}
+
+#[test]
+fn program_shared_across_threads(){
+    let program = Arc::new(Program::new(assemble("gload x\ngload x\nmul\n", "test.zt").unwrap()));
+    let handles: Vec<_> = (0..4)
+        .map(|x| {
+            let program = Arc::clone(&program);
+            thread::spawn(move || {
+                let mut vm = Vm::new(program);
+                vm.run_with_globals([("x", Vobj::Int(x))]).unwrap()
+            })
+        })
+        .collect();
+    let results: Vec<Vec<Vobj>> = handles.into_iter().map(|handle| handle.join().unwrap()).collect();
+    assert_eq!(vec![vec![Vobj::Int(0)], vec![Vobj::Int(1)], vec![Vobj::Int(4)], vec![Vobj::Int(9)]], results);
+}
+
+#[test]
+fn append_does_not_affect_shared_program(){
+    let program = Arc::new(Program::new(assemble("load 1\n", "test.zt").unwrap()));
+    let mut vm = Vm::new(Arc::clone(&program));
+    vm.append(assemble("load 2\n", "test.zt").unwrap());
+    assert_eq!(2, vm.code().len());
+    assert_eq!(1, program.len());
+}
+
+#[test]
+fn program_debug_info(){
+    let program = assemble_program("load 1\nloop: load \"a\"\nbr loop\n", "test.zt").unwrap();
+    assert_eq!(Some("loop"), program.label(1));
+    assert_eq!(None, program.label(0));
+    let constants: Vec<&Vobj> = program.constants().collect();
+    assert_eq!(vec![&Vobj::Int(1), &Vobj::from("a")], constants);
+    let vm = Vm::builder(Vec::new()).build().unwrap();
+    assert!(vm.program().is_empty());
+}
@@ -0,0 +1,104 @@
+use std::collections::HashSet;
+
+use vm::asm::{assemble, format_value, parse_value};
+use vm::bytecode;
+use vm::err::{Position, VMError};
+use vm::vm::Vm;
+use vm::vobj::{IntRange, Vobj, VobjKind};
+
+mod common;
+use common::run;
+
+fn range(start: i64, stop: i64, step: i64) -> IntRange {
+    IntRange::new(start, stop, step).unwrap()
+}
+
+#[test]
+fn length_and_indexing(){
+    assert_eq!(10, range(0, 10, 1).len());
+    assert_eq!(3, range(1, 10, 3).len());
+    assert_eq!(5, range(10, 0, -2).len());
+    assert!(range(5, 5, 1).is_empty());
+    assert!(range(0, 5, -1).is_empty());
+    assert_eq!(u64::MAX, range(i64::MIN, i64::MAX, 1).len());
+    assert_eq!(Some(7), range(1, 10, 3).get(2));
+    assert_eq!(None, range(1, 10, 3).get(3));
+    assert_eq!(Some(2), range(10, 0, -2).get(4));
+    assert_eq!(Some(i64::MAX - 1), range(i64::MIN, i64::MAX, 1).get(u64::MAX - 1));
+    assert_eq!(Err(VMError::IncorrectArgumentErr("range step must not be zero".to_string())), IntRange::new(0, 1, 0));
+}
+
+#[test]
+fn membership(){
+    let evens = range(0, 10, 2);
+    assert_eq!(Some(3), evens.position(6));
+    assert_eq!(None, evens.position(7));
+    assert_eq!(None, evens.position(10));
+    assert_eq!(None, evens.position(-2));
+    assert_eq!(Some(1), range(10, 0, -3).position(7));
+    assert_eq!(Ok(Vobj::Bool(true)), Vobj::contains(&Vobj::Range(evens), &Vobj::Double(4.0)));
+    assert_eq!(Ok(Vobj::Bool(false)), Vobj::contains(&Vobj::Range(evens), &Vobj::from("4")));
+    assert_eq!(Ok(Vobj::Int(2)), Vobj::index_of(&Vobj::Range(evens), &Vobj::Int(4)));
+    assert_eq!(Ok(Vobj::Null), Vobj::index_of(&Vobj::Range(evens), &Vobj::Int(5)));
+}
+
+#[test]
+fn equal_ranges_hold_the_same_ints(){
+    assert_eq!(range(0, 3, 2), range(0, 4, 2));
+    assert_eq!(range(5, 5, 1), range(0, -3, 7));
+    assert_eq!(range(3, 4, 1), range(3, 4, 5));
+    assert!(Vobj::List(vec![Vobj::Int(9)]) < Vobj::Range(range(0, 1, 1)));
+    assert_ne!(range(0, 3, 1), range(0, 3, 2));
+    let set: HashSet<Vobj> = [Vobj::Range(range(0, 3, 2)), Vobj::Range(range(0, 4, 2)), Vobj::Range(range(0, 4, 1))].into_iter().collect();
+    assert_eq!(2, set.len());
+}
+
+#[test]
+fn literals(){
+    assert_eq!(Ok(Vobj::Range(range(0, 10, 1))), parse_value("range(0, 10)"));
+    assert_eq!(Ok(Vobj::Range(range(10, 0, -2))), parse_value("range(10, 0, -2)"));
+    assert_eq!("range(10, 0, -2)", format_value(&Vobj::Range(range(10, 0, -2))));
+    assert_eq!("range(0, 10)", Vobj::Range(range(0, 10, 1)).to_string());
+    assert_eq!(Ok(Vobj::List(vec![Vobj::Range(range(0, 2, 1)), Vobj::Int(1)])), parse_value("[range(0, 2), 1]"));
+    assert!(parse_value("range(0, 1, 0)").is_err());
+    assert!(parse_value("range(0)").is_err());
+    assert!(parse_value("range(0, x)").is_err());
+
+    let code = assemble("load range(-5, 5, 3)\n", "test.zt").unwrap();
+    assert_eq!(code, bytecode::decode(&bytecode::encode(&code)).unwrap());
+}
+
+#[test]
+fn range_instructions(){
+    assert_eq!(vec![Vobj::Range(range(2, 8, 1))], run("load 2\nload 8\nrange\n"));
+    assert_eq!(vec![Vobj::Int(3), Vobj::Int(6)], run("load 0\nload 9\nload 3\nrangestep\nlen\nload range(0, 9, 3)\nload 2\nget\n"));
+    let vm = Vm::load(assemble("load 0\nload 1\nload 0\nrangestep\n", "test.zt").unwrap());
+    assert_eq!("incorrect argument error: range step must not be zero", vm.run().unwrap_err().error.to_string());
+    assert_eq!(
+        Err(VMError::TypeErr { operation: "RangeStep", position: Position::Argument(3), expected: vec![VobjKind::Int], actual: VobjKind::Double }),
+        Vobj::range_step(&Vobj::Int(0), &Vobj::Int(1), &Vobj::Double(1.0))
+    );
+    let vm = Vm::load(assemble("load range(0, 3)\nload 3\nget\n", "test.zt").unwrap());
+    assert_eq!(VMError::IndexOutOfRangeErr { index: 3, len: 3 }, vm.run().unwrap_err().error);
+}
+
+#[test]
+fn loops_over_a_range(){
+    let text = "\
+        load 0
+        gstore total
+        load 10
+        load 0
+        load -3
+        rangestep
+        iternew
+loop:   iternext done
+        gload total
+        add
+        gstore total
+        br loop
+done:   gload total
+";
+    // 10 + 7 + 4 + 1
+    assert_eq!(vec![Vobj::Int(22)], run(text));
+}
@@ -0,0 +1,21 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+// Feeds `input` to the repl binary and returns what it printed.
+fn repl(input: &str) -> String {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_vm-repl"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(input.as_bytes()).unwrap();
+    let output = child.wait_with_output().unwrap();
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn typed_labels_are_relocated(){
+    let output = repl("load 5\nload 0\nx: bnull x\n:program\n");
+    assert!(output.contains("bnull 2"), "{}", output);
+    assert!(!output.contains("bnull 0"), "{}", output);
+}
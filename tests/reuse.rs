@@ -0,0 +1,58 @@
+use vm::asm::assemble;
+use vm::vm::*;
+use vm::err::VMError;
+use vm::vobj::Vobj;
+
+#[test]
+fn reset_restores_state(){
+    let code = assemble("load 1\nadd\ngstore total\nload \"done\"\n", "test.zt").unwrap();
+    let mut vm = Vm::builder(code).stack(vec![Vobj::Int(41)]).build().unwrap();
+    vm.run().unwrap();
+    assert_eq!(Some(Vobj::Int(42)), vm.global("total"));
+    assert!(vm.is_finished());
+
+    vm.reset();
+    assert_eq!(0, vm.pc());
+    assert_eq!(None, vm.global("total"));
+    assert_eq!(vec![Vobj::Int(41)], *vm.dump_mem());
+    vm.run().unwrap();
+    assert_eq!(Some(Vobj::Int(42)), vm.global("total"));
+}
+
+#[test]
+fn run_with_args(){
+    let code = assemble("mul\n", "test.zt").unwrap();
+    let mut vm = Vm::load(code);
+    for (a, b) in [(2, 3), (4, 5), (6, 7)] {
+        let result = vm.run_with(vec![Vobj::Int(a), Vobj::Int(b)]).unwrap();
+        assert_eq!(vec![Vobj::Int(a * b)], result);
+    }
+}
+
+#[test]
+fn run_with_globals(){
+    let code = assemble("gload price\ngload qty\nmul\n", "test.zt").unwrap();
+    let mut vm = Vm::load(code);
+    let result = vm.run_with_globals([("price", Vobj::Double(2.5)), ("qty", Vobj::Int(4))]).unwrap();
+    assert_eq!(vec![Vobj::Double(10.0)], result);
+    let result = vm.run_with_globals([("price", Vobj::Int(3)), ("qty", Vobj::Int(3))]).unwrap();
+    assert_eq!(vec![Vobj::Int(9)], result);
+    let error = vm.run_with_globals([("price", Vobj::Int(3))]).unwrap_err();
+    assert_eq!(VMError::UndefinedGlobalErr("qty".to_string()), error.error);
+    assert_eq!(1, error.pc);
+}
+
+#[test]
+fn reset_restores_fuel(){
+    let code = assemble("load 1\nload 2\nadd\n", "test.zt").unwrap();
+    let mut vm = Vm::builder(code).fuel(3).build().unwrap();
+    assert_eq!(vec![Vobj::Int(3)], vm.run_with(vec![]).unwrap());
+    assert_eq!(Some(0), vm.fuel());
+    assert_eq!(vec![Vobj::Int(3)], vm.run_with(vec![]).unwrap());
+
+    vm.reset();
+    vm.set_fuel(Some(2));
+    assert_eq!(RunOutcome::OutOfFuel, vm.run_budgeted().unwrap());
+    vm.reset();
+    assert_eq!(Some(3), vm.fuel());
+}
@@ -0,0 +1,26 @@
+use std::sync::Arc;
+
+use vm::asm::assemble;
+use vm::err::Source;
+
+#[test]
+fn source_constructors(){
+    let src = Source::new("test.zt", 3);
+    assert_eq!("test.zt:3", src.to_string());
+    assert_eq!(None, src.span());
+    assert_eq!(src, Source::new(String::from("test.zt"), 3));
+
+    let src = Source::with_span("test.zt", 3, 5, 4);
+    assert_eq!("test.zt:3:5", src.to_string());
+    assert_eq!(Some(5), src.column());
+    assert_eq!(Some(5..9), src.span());
+}
+
+#[test]
+fn assembled_sources_share_file_name(){
+    let code = assemble("load 1\n  add\n", &String::from("owned.zt")).unwrap();
+    let first = code[0].src();
+    let second = code[1].src();
+    assert!(Arc::ptr_eq(first.shared_source_file(), second.shared_source_file()));
+    assert_eq!(Some(3..6), second.span());
+}
@@ -0,0 +1,66 @@
+use vm::asm::assemble;
+use vm::trace::*;
+use vm::vm::Vm;
+use vm::vobj::Vobj;
+
+#[test]
+fn collector_records_execution_path(){
+    let code = assemble("load 2\ncall f\nbr end\nf: load 3\nmul\nret\nend: nop\n", "test.zt").unwrap();
+    let mut vm = Vm::load(code);
+    let collector = TraceCollector::new();
+    vm.add_observer(Box::new(collector.clone()));
+    vm.run().unwrap();
+    assert_eq!(vec![0, 1, 3, 4, 5, 2, 6], collector.executed_pcs());
+    let events = collector.events();
+    assert!(events.contains(&TraceEvent::Call { pc: 1, target: 3 }));
+    assert!(events.contains(&TraceEvent::Return { pc: 5, return_pc: 2 }));
+    assert_eq!(
+        events[0],
+        TraceEvent::Executed {
+            pc: 0,
+            instruction: "load 2".to_string(),
+            src: "test.zt:1:1".to_string(),
+            stack: vec![Vobj::Int(2)],
+        }
+    );
+}
+
+#[test]
+fn collector_records_errors(){
+    let code = assemble("load 1\nload 0\ndiv\n", "test.zt").unwrap();
+    let mut vm = Vm::load(code);
+    let collector = TraceCollector::new();
+    vm.add_observer(Box::new(collector.clone()));
+    assert!(vm.run().is_err());
+    let events = collector.events();
+    assert_eq!(
+        events.last(),
+        Some(&TraceEvent::Error {
+            pc: 2,
+            instruction: "div".to_string(),
+            src: "test.zt:3:1".to_string(),
+            error: "Division by zero error".to_string(),
+        })
+    );
+}
+
+#[test]
+fn collector_records_caught_errors(){
+    let code = assemble("call outer\nbr end\nouter: trystart handler\ncall inner\nret\ninner: load \"bad data\"\nthrow\nhandler: ret\nend: nop\n", "test.zt").unwrap();
+    let mut vm = Vm::load(code);
+    let collector = TraceCollector::new();
+    vm.add_observer(Box::new(collector.clone()));
+    vm.run().unwrap();
+    assert_eq!(vec![0, 2, 3, 5, 7, 1, 8], collector.executed_pcs());
+    let events = collector.events();
+    let error = events.iter().position(|event| matches!(event, TraceEvent::Error { pc: 6, .. })).unwrap();
+    assert_eq!(TraceEvent::Unwind { pc: 6, depth: 1 }, events[error + 1]);
+    assert!(events.contains(&TraceEvent::Return { pc: 7, return_pc: 1 }));
+}
+
+#[test]
+fn ret_without_call(){
+    let code = assemble("ret\n", "test.zt").unwrap();
+    let vm = Vm::load(code);
+    assert!(vm.run().is_err());
+}
@@ -0,0 +1,49 @@
+use vm::asm::assemble;
+use vm::err::{Position, Source, VMError};
+use vm::instruction::Instruction;
+use vm::vm::Vm;
+use vm::vobj::{Vobj, VobjKind};
+
+mod common;
+use common::run;
+
+#[test]
+fn type_of_keeps_the_value(){
+    assert_eq!(vec![Vobj::Int(1), Vobj::from("Int")], run("load 1\ntypeof\n"));
+    assert_eq!(vec![Vobj::Null, Vobj::from("Null")], run("load null\ntypeof\n"));
+    assert_eq!(vec![Vobj::Bool(true), Vobj::from("Bool")], run("load true\ntypeof\n"));
+}
+
+#[test]
+fn predicates(){
+    assert_eq!(vec![Vobj::Null, Vobj::Bool(true)], run("load null\nisnull\n"));
+    assert_eq!(vec![Vobj::Double(1.5), Vobj::Bool(false)], run("load 1.5\nisint\n"));
+    assert_eq!(vec![Vobj::Double(1.5), Vobj::Bool(true)], run("load 1.5\nisdouble\n"));
+    assert_eq!(vec![Vobj::from("a"), Vobj::Bool(true)], run("load \"a\"\nisstr\n"));
+    assert_eq!(vec![Vobj::Bool(false), Vobj::Bool(true)], run("load false\nisbool\n"));
+    let caught = run("trystart c\nload 1\nload 0\ndiv\ntryend\nc: iserror\n");
+    assert_eq!(VobjKind::Error, caught[0].kind());
+    assert_eq!(Vobj::Bool(true), caught[1]);
+}
+
+#[test]
+fn assert_type(){
+    assert_eq!(vec![Vobj::Int(3)], run("load 3\nasserttype Int\n"));
+
+    let vm = Vm::load(assemble("load \"3\"\nasserttype Int\n", "test.zt").unwrap());
+    let error = vm.run().unwrap_err();
+    assert_eq!(
+        VMError::TypeErr { operation: "AssertType", position: Position::Only, expected: vec![VobjKind::Int], actual: VobjKind::Str },
+        error.error
+    );
+    assert_eq!("AssertType: operand is Str, expected Int", error.error.to_string());
+    assert_eq!(vec![Vobj::from("3")], error.stack);
+}
+
+#[test]
+fn assert_type_kind_is_checked_when_assembling(){
+    let error = assemble("load 1\nasserttype Integer\n", "test.zt").unwrap_err();
+    assert_eq!(2, error.line);
+    assert!(error.message.contains("unknown kind `Integer`"), "{}", error.message);
+    assert_eq!("AssertType Double", Instruction::assert_type(VobjKind::Double, Source::new("test.zt", 0)).str_repr());
+}
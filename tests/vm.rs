@@ -1,11 +1,14 @@
+#![allow(clippy::assertions_on_constants)]
 
 #[cfg(test)]
 mod test {
 
     use vm::vm::*;
-    use vm::err::Source;
+    use vm::err::{Source, VMError};
     use vm::vobj::*;
     use vm::instruction::Instruction;
+    use vm::program::Program;
+    use std::time::{Duration, Instant};
 
 
     #[test]
@@ -244,4 +247,159 @@ mod test {
             assert!(false,"empty stack")
         }
     }
+
+    #[test]
+    fn vm_append_rewind_test() {
+        let src = Source::new("test.zt", 0);
+        let mut vm = Vm::load(vec![Instruction::load_instruction(Vobj::Int(2), src.clone())]);
+        vm.run().unwrap();
+        let before = vm.snapshot();
+        vm.append(vec![
+            Instruction::load_instruction(Vobj::Int(5), src.clone()),
+            Instruction::gstore("x", src.clone()),
+            Instruction::try_start(4, src.clone()),
+            Instruction::nop_instruction(src.clone()),
+        ]);
+        vm.run().unwrap();
+        vm.rewind(before.clone());
+        assert_eq!(vm.pc(), 1);
+        assert_eq!(vm.global("x"), None);
+        vm.append(vec![
+            Instruction::load_instruction(Vobj::Int(0), src.clone()),
+            Instruction::div(src.clone()),
+        ]);
+        assert!(vm.run().is_err());
+        vm.rewind(before);
+        vm.append(vec![Instruction::load_instruction(Vobj::Int(3), src.clone()), Instruction::mul(src)]);
+        vm.run().unwrap();
+        assert_eq!(*vm.dump_mem(), vec![Vobj::Int(6)]);
+    }
+
+    #[test]
+    fn vm_fuel_test() {
+        let src = Source::new("test.zt", 0);
+        let code = vec![
+            Instruction::load_instruction(Vobj::Int(1), src.clone()),
+            Instruction::load_instruction(Vobj::Int(2), src.clone()),
+            Instruction::add(src),
+        ];
+        let mut vm = Vm::load(code);
+        vm.set_fuel(Some(2));
+        assert_eq!(vm.run_budgeted(), Ok(RunOutcome::OutOfFuel));
+        assert_eq!(vm.pc(), 2);
+        assert!(vm.run().is_err());
+        vm.add_fuel(1);
+        assert_eq!(vm.run_budgeted(), Ok(RunOutcome::Finished));
+        assert_eq!(*vm.dump_mem(), vec![Vobj::Int(3)]);
+        assert_eq!(vm.instructions_executed(), 3);
+    }
+
+    #[test]
+    fn vm_infinite_loop_fuel_test() {
+        let src = Source::new("test.zt", 0);
+        let mut vm = Vm::load(vec![Instruction::br(0, src)]);
+        vm.set_fuel(Some(1000));
+        assert_eq!(vm.run_budgeted(), Ok(RunOutcome::OutOfFuel));
+        assert_eq!(vm.instructions_executed(), 1000);
+    }
+
+    #[test]
+    fn vm_deadline_test() {
+        let src = Source::new("test.zt", 0);
+        let mut vm = Vm::load(vec![Instruction::nop_instruction(src.clone()), Instruction::br(0, src)]);
+        vm.set_deadline(Some(Instant::now() + Duration::from_millis(20)));
+        assert_eq!(vm.run_budgeted(), Ok(RunOutcome::DeadlineExceeded));
+    }
+
+    #[test]
+    fn vm_stack_limit_test() {
+        let src = Source::new("test.zt", 0);
+        let code = vec![Instruction::load_instruction(Vobj::Int(1), src.clone()), Instruction::br(0, src)];
+        let config = VmConfig { max_stack_depth: 8, ..VmConfig::default() };
+        let vm = Vm::with_config(code, config);
+        let result = vm.run();
+        assert!(result.unwrap_err().error.to_string().starts_with("limit exceeded: operand stack"));
+        assert_eq!(vm.dump_mem().len(), 8);
+    }
+
+    #[test]
+    fn vm_heap_limit_test() {
+        let src = Source::new("test.zt", 0);
+        let code = vec![
+            Instruction::load_instruction(Vobj::from("abcd"), src.clone()),
+            Instruction::load_instruction(Vobj::from("efgh"), src.clone()),
+            Instruction::load_instruction(Vobj::from("ijkl"), src),
+        ];
+        let config = VmConfig { max_heap_bytes: 10, ..VmConfig::default() };
+        let vm = Vm::with_config(code, config);
+        let result = vm.run();
+        assert!(result.unwrap_err().error.to_string().starts_with("limit exceeded: heap"));
+        assert_eq!(vm.dump_mem().len(), 2);
+    }
+
+    #[test]
+    fn vm_call_depth_limit_test() {
+        let src = Source::new("test.zt", 0);
+        let config = VmConfig { max_call_depth: 16, ..VmConfig::default() };
+        let vm = Vm::with_config(vec![Instruction::call(0, src)], config);
+        let result = vm.run();
+        assert!(result.unwrap_err().error.to_string().starts_with("limit exceeded: call depth"));
+        assert_eq!(vm.frames().len(), 16);
+    }
+
+    #[test]
+    fn vm_step_after_finish_test() {
+        let src = Source::new("test.zt", 0);
+        let vm = Vm::load(vec![Instruction::load_instruction(Vobj::Int(1), src)]);
+        vm.step().unwrap();
+        assert!(vm.is_finished());
+        vm.step().unwrap();
+        assert_eq!(vm.pc(), 1);
+        assert_eq!(*vm.dump_mem(), vec![Vobj::Int(1)]);
+    }
+
+    #[test]
+    fn vm_ordered_branch_target_test() {
+        let src = Source::new("test.zt", 0);
+        let code = vec![
+            Instruction::load_instruction(Vobj::Int(2), src.clone()),
+            Instruction::load_instruction(Vobj::Int(1), src.clone()),
+            Instruction::bgt(3, src.clone()),
+        ];
+        let vm = Vm::load(code.clone());
+        vm.run().unwrap();
+        assert!(vm.is_finished());
+
+        let mut code = code;
+        code[2] = Instruction::bgt(100, src.clone());
+        let vm = Vm::load(code.clone());
+        let error = vm.run().unwrap_err();
+        assert_eq!(error.error, VMError::InvalidBranchErr(100));
+        assert_eq!(error.pc, 2);
+
+        // the same rule holds for every branch
+        code[2] = Instruction::br(3, src.clone());
+        Vm::load(code.clone()).run().unwrap();
+        code[2] = Instruction::br(4, src);
+        assert_eq!(Vm::load(code).run().unwrap_err().error, VMError::InvalidBranchErr(4));
+    }
+
+    #[test]
+    fn vm_bgt_variant_test() {
+        let src = Source::new("test.zt", 0);
+        let code = vec![
+            Instruction::load_instruction(Vobj::Int(2), src.clone()),
+            Instruction::load_instruction(Vobj::Int(1), src.clone()),
+            Instruction::Bgt { br_index: 4, str_repr: "Bgt 4".to_string(), src: src.clone() },
+            Instruction::load_instruction(Vobj::from("not taken"), src.clone()),
+            Instruction::nop_instruction(src.clone()),
+        ];
+        let vm = Vm::load(code.clone());
+        vm.run().unwrap();
+        assert!(vm.dump_mem().is_empty());
+
+        let mut code = code;
+        code[2] = Instruction::Bgt { br_index: 9, str_repr: "Bgt 9".to_string(), src };
+        assert_eq!(Program::new(code).verify().unwrap_err().len(), 1);
+    }
 }
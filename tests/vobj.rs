@@ -1,3 +1,5 @@
+#![allow(clippy::assertions_on_constants)]
+
 use vm::vobj::*;
 use vm::err::*;
 
@@ -94,3 +96,24 @@ fn less_eq(){
     let expected=true;
     assert_eq!(expected,result);
 }
+
+#[test]
+fn type_of(){
+    assert_eq!("Int",Vobj::Int(1).type_of());
+    assert_eq!("Double",Vobj::Double(1.0).type_of());
+    assert_eq!("Str",Vobj::from("a").type_of());
+    assert_eq!("Null",Vobj::Null.type_of());
+    assert_eq!(VobjKind::Str,Vobj::from("a").kind());
+    assert_eq!("Error",VobjKind::Error.to_string());
+}
+
+#[test]
+fn type_error_names_operand(){
+    let result=Vobj::add(&Vobj::Int(1), &Vobj::from("a"));
+    assert_eq!("Add: right operand is Str, expected Int or Double",result.unwrap_err().to_string());
+    let result=Vobj::mul(&Vobj::Null, &Vobj::Int(1));
+    assert_eq!("Mul: left operand is Null, expected Int or Double",result.unwrap_err().to_string());
+    let result=Vobj::div(&Vobj::Double(1.0), &Vobj::from("0"));
+    let expected=VMError::TypeErr{operation:"Div",position:Position::Right,expected:vec![VobjKind::Int,VobjKind::Double],actual:VobjKind::Str};
+    assert_eq!(Err(expected),result);
+}